@@ -0,0 +1,100 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{GenericArgument, Ident, ItemImpl, PathArguments, ReturnType, Type};
+
+use crate::controller::client_trait::{self, TraitMethod};
+
+/// Generate a blocking client, wrapping all the methods of the client (except for its
+/// constructor) in blocking calls, and its streams in blocking iterators.
+pub(crate) fn generate(client_impl: &ItemImpl, struct_name: &Ident) -> TokenStream {
+    let client_name = &client_impl.self_ty;
+    let blocking_client_name =
+        Ident::new(&format!("{struct_name}BlockingClient"), struct_name.span());
+
+    let methods = client_trait::trait_methods(client_impl);
+    let methods = methods
+        .iter()
+        .map(|method| generate_method(method, client_name));
+
+    quote! {
+        /// A client blocking the current thread on each call, for use outside of an async
+        /// context. It must not be used from the thread running the controller.
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct #blocking_client_name;
+
+        impl #blocking_client_name {
+            pub const fn new() -> Self {
+                Self
+            }
+
+            #(#methods)*
+        }
+    }
+}
+
+fn generate_method(method: &TraitMethod, client_name: &Type) -> TokenStream {
+    let TraitMethod { sig, args, docs } = method;
+    let mut sig = sig.clone();
+    let method_name = &sig.ident;
+    let client = match sig.receiver() {
+        Some(receiver) if receiver.mutability.is_some() => quote! { &mut #client_name::new() },
+        _ => quote! { &#client_name::new() },
+    };
+    let mut call = quote! { #client_name::#method_name(#client, #(#args),*) };
+    if sig.asyncness.take().is_some() {
+        call = quote! { futures::executor::block_on(#call) };
+    }
+
+    if let ReturnType::Type(_, ty) = &mut sig.output {
+        if is_stream(ty) {
+            // `subscribe_<stream>`.
+            **ty = syn::parse_quote! { futures::executor::BlockingStream<#ty> };
+            call = quote! { futures::executor::block_on_stream(#call) };
+        } else if let Some(ok_type) = result_ok_type(ty) {
+            if is_stream(ok_type) {
+                // `receive_<stream>`.
+                *ok_type = syn::parse_quote! { futures::executor::BlockingStream<#ok_type> };
+                call =
+                    quote! { core::result::Result::map(#call, futures::executor::block_on_stream) };
+            }
+        }
+    }
+
+    quote! {
+        #(#docs)*
+        pub #sig {
+            #call
+        }
+    }
+}
+
+/// Whether the type is one of the streams of the client.
+fn is_stream(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "Published" || segment.ident == "SignalStream")
+}
+
+/// The `T` of a `Result<T, E>` type.
+fn result_ok_type(ty: &mut Type) -> Option<&mut Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last_mut()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &mut segment.arguments else {
+        return None;
+    };
+
+    match args.args.first_mut()? {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
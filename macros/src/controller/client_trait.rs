@@ -0,0 +1,124 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, FnArg, Ident, ImplItem, ItemImpl, Pat, Signature, Visibility};
+
+/// Name of the trait implemented by the client.
+pub(crate) fn trait_ident(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{struct_name}Api"), struct_name.span())
+}
+
+/// A method of the client trait.
+pub(crate) struct TraitMethod {
+    /// The signature, with plain identifiers as argument patterns.
+    pub sig: Signature,
+    /// The argument names.
+    pub args: Vec<Ident>,
+    /// The doc attributes of the client method.
+    pub docs: Vec<Attribute>,
+}
+
+impl TraitMethod {
+    /// The call of the client's inherent method on the given client.
+    pub fn client_call(&self, client_name: &syn::Type, client: TokenStream) -> TokenStream {
+        let method_name = &self.sig.ident;
+        let args = &self.args;
+        let call = quote! { #client_name::#method_name(#client, #(#args),*) };
+
+        match self.sig.asyncness {
+            Some(_) => quote! { #call.await },
+            None => call,
+        }
+    }
+}
+
+/// The methods of the client that are part of the trait: all public methods except for the
+/// constructor.
+pub(crate) fn trait_methods(client_impl: &ItemImpl) -> Vec<TraitMethod> {
+    client_impl
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            ImplItem::Fn(method) if matches!(method.vis, Visibility::Public(_)) => Some(method),
+            _ => None,
+        })
+        .filter(|method| method.sig.receiver().is_some())
+        .map(|method| {
+            let mut sig = method.sig.clone();
+            // Trait methods can't be const.
+            sig.constness = None;
+            let args = plain_args(&mut sig);
+            let docs = method
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("doc"))
+                .cloned()
+                .collect();
+
+            TraitMethod { sig, args, docs }
+        })
+        .collect()
+}
+
+/// Generate a trait with all the methods of the client (except for its constructor), along with
+/// its implementation for the client, so that consumers can be generic over the client.
+///
+/// The trait methods delegate to the inherent methods of the client, so that the client can still
+/// be used without importing the trait.
+pub(crate) fn generate(client_impl: &ItemImpl, struct_name: &Ident) -> TokenStream {
+    let trait_name = trait_ident(struct_name);
+    let client_name = &client_impl.self_ty;
+
+    let (declarations, implementations): (Vec<_>, Vec<_>) = trait_methods(client_impl)
+        .iter()
+        .map(|method| {
+            let TraitMethod { sig, docs, .. } = method;
+            let call = method.client_call(client_name, quote! { self });
+
+            (
+                quote! {
+                    #(#docs)*
+                    #sig;
+                },
+                quote! {
+                    #sig {
+                        #call
+                    }
+                },
+            )
+        })
+        .unzip();
+
+    quote! {
+        // Consumers are not expected to need `Send` futures in a `no_std` environment.
+        #[allow(async_fn_in_trait)]
+        pub trait #trait_name {
+            #(#declarations)*
+        }
+
+        impl #trait_name for #client_name {
+            #(#implementations)*
+        }
+    }
+}
+
+/// Replace the argument patterns of the signature with plain identifiers, as patterns aren't
+/// allowed in trait method declarations, and return the identifiers.
+fn plain_args(sig: &mut Signature) -> Vec<Ident> {
+    sig.inputs
+        .iter_mut()
+        .filter_map(|arg| match arg {
+            FnArg::Typed(arg) => Some(arg),
+            FnArg::Receiver(_) => None,
+        })
+        .enumerate()
+        .map(|(i, arg)| {
+            let name = match &*arg.pat {
+                Pat::Ident(pat) => pat.ident.clone(),
+                _ => Ident::new(&format!("arg{i}"), proc_macro2::Span::call_site()),
+            };
+            *arg.pat = syn::parse_quote! { #name };
+
+            name
+        })
+        .collect()
+}
@@ -0,0 +1,1525 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    spanned::Spanned,
+    Attribute, Ident, ImplItem, ImplItemFn, ItemImpl, Result, Signature, Token, Visibility,
+};
+
+use crate::controller::item_struct::{
+    batch_idents, clamp_code, out_of_range_ident, ExpandedStruct, GetterFieldInfo,
+    PublishedFieldInfo, SetterFieldInfo, SnapshotInfo,
+};
+use crate::controller::subscription::{channel_ref, ClientStream};
+use crate::controller::{blocking_client, client_trait, mock, ControllerArgs};
+use crate::util::snake_to_pascal_case;
+
+pub(crate) fn expand(
+    mut input: ItemImpl,
+    expanded_struct: &ExpandedStruct,
+    args: &ControllerArgs,
+) -> Result<TokenStream> {
+    let published_fields = &expanded_struct.published_fields;
+    let getter_fields = &expanded_struct.getter_fields;
+    let setter_fields = &expanded_struct.setter_fields;
+    let changes_stream = expanded_struct.changes_stream.as_ref();
+
+    let struct_name = get_struct_name(&input)?;
+    let struct_name_str = struct_name.to_string();
+    let client_name = Ident::new(&format!("{}Client", struct_name_str), input.span());
+    let batched_methods = split_atomic_methods(&mut input, &struct_name)?;
+    let unproxied_methods = expanded_struct
+        .hook_methods
+        .iter()
+        .chain(&batched_methods)
+        .cloned()
+        .collect::<Vec<_>>();
+    let methods = get_methods(&mut input, &struct_name, &unproxied_methods)?;
+
+    let signals = methods.iter().filter_map(|m| match m {
+        Method::Signal(signal) => Some(signal),
+        _ => None,
+    });
+    let signal_declarations = signals.clone().map(|s| &s.declarations);
+    let events = generate_events(signals.clone(), &struct_name);
+
+    let methods = methods.iter().filter_map(|m| match m {
+        Method::Proxied(method) => Some(method),
+        _ => None,
+    });
+    let args_channel_declarations = methods.clone().map(|m| &m.args_channel_declarations);
+    let args_channels_rx_tx = methods.clone().map(|m| &m.args_channels_rx_tx);
+
+    // Generate public setters for fields with setter attribute.
+    let pub_setters = setter_fields
+        .iter()
+        .map(|field| generate_pub_setter(field, &struct_name, &input))
+        .collect::<Result<Vec<_>>>()?;
+    let pub_setter_channel_declarations = pub_setters.iter().map(|s| &s.channel_declarations);
+    let pub_setter_rx_tx = pub_setters.iter().map(|s| &s.rx_tx);
+    let pub_setter_client_methods = pub_setters.iter().map(|s| &s.client_method);
+
+    // Generate public getters for fields with getter attribute.
+    // The snapshot is also fetched like a getter.
+    let pub_getters: Vec<_> = getter_fields
+        .iter()
+        .map(|field| generate_pub_getter(field, &struct_name))
+        .chain(
+            expanded_struct
+                .snapshot
+                .as_ref()
+                .map(|snapshot| generate_snapshot_getter(snapshot, &struct_name)),
+        )
+        .collect();
+    let pub_getter_channel_declarations = pub_getters.iter().map(|g| &g.channel_declarations);
+    let pub_getter_rx_tx = pub_getters.iter().map(|g| &g.rx_tx);
+    let pub_getter_client_methods = pub_getters.iter().map(|g| &g.client_method);
+
+    let handlers = methods
+        .clone()
+        .map(|m| &m.handler)
+        .chain(pub_setters.iter().map(|s| &s.handler))
+        .chain(pub_getters.iter().map(|g| &g.handler))
+        .collect::<Vec<_>>();
+    let select_arms = handlers.iter().map(|h| h.select_arm());
+    let pending_handlers = handlers.iter().map(|h| h.try_handle());
+
+    // Generate the select arms and due flushes for pending broadcasts of rate-limited published
+    // fields.
+    let pending_broadcasts = published_fields
+        .iter()
+        .filter_map(|field| field.pending_broadcast.as_ref())
+        .collect::<Vec<_>>();
+    // Copied out, so that the arm's future doesn't borrow `self`.
+    let pending_broadcast_deadlines = pending_broadcasts.iter().map(|pending| {
+        let deadline = &pending.deadline_field_name;
+        quote! { let #deadline = self.#deadline; }
+    });
+    let pending_broadcast_select_arms = pending_broadcasts.iter().map(|pending| {
+        let deadline = &pending.deadline_field_name;
+        let flush = &pending.flush_method_name;
+        quote! {
+            _ = futures::FutureExt::fuse(async move {
+                match #deadline {
+                    Some(deadline) => embassy_time::Timer::at(deadline).await,
+                    None => core::future::pending().await,
+                }
+            }) => {
+                self.#flush();
+            }
+        }
+    });
+    let pending_broadcast_flushes = pending_broadcasts.iter().map(|pending| {
+        let deadline = &pending.deadline_field_name;
+        let flush = &pending.flush_method_name;
+        quote! {
+            if let Some(deadline) = self.#deadline {
+                if deadline <= embassy_time::Instant::now() {
+                    self.#flush();
+                }
+            }
+        }
+    });
+
+    let channel_clears = handlers.iter().map(|h| h.clear());
+    let signal_last_resets = signals.clone().map(|s| &s.last_reset);
+    let published_field_resets = published_fields.iter().map(|field| {
+        let watch_clear = field.stream.clear();
+        let atomic_reset = &field.atomic_reset;
+        quote! {
+            #watch_clear
+            #atomic_reset
+        }
+    });
+    let snapshot_reset = expanded_struct
+        .snapshot
+        .as_ref()
+        .and_then(|snapshot| snapshot.stream.as_ref())
+        .and_then(|stream| stream.clear());
+    let controller_impl = quote! {
+        impl firmware_controller::Controller for #struct_name {
+            type Client = #client_name;
+
+            fn run(self) -> impl core::future::Future<Output = ()> {
+                #struct_name::run(self)
+            }
+
+            fn reset() {
+                #(#channel_clears)*
+                #(#signal_last_resets)*
+                #(#published_field_resets)*
+                #snapshot_reset
+            }
+        }
+    };
+
+    let rx_tx = quote! {
+        #(#args_channels_rx_tx)*
+        #(#pub_setter_rx_tx)*
+        #(#pub_getter_rx_tx)*
+    };
+    // Without any client method nor rate-limited field, there's nothing to wait for.
+    let run_loop = if handlers.is_empty() && pending_broadcasts.is_empty() {
+        quote! { core::future::pending::<()>().await; }
+    } else {
+        quote! {
+            loop {
+                #(#pending_broadcast_deadlines)*
+
+                futures::select_biased! {
+                    #(#select_arms,)*
+                    #(#pending_broadcast_select_arms,)*
+                }
+            }
+        }
+    };
+    let run_method = quote! {
+        pub async fn run(mut self) {
+            #rx_tx
+
+            #run_loop
+        }
+    };
+    input.items.push(syn::parse2(run_method)?);
+
+    // Generate the methods driving the controller without an executor.
+    let process_pending_method = quote! {
+        /// Handle all the pending requests from the clients, and the due broadcasts of
+        /// rate-limited fields, without waiting for more. Returns the number of requests handled.
+        pub async fn process_pending(&mut self) -> usize {
+            #rx_tx
+
+            let mut handled = 0;
+            loop {
+                #(#pending_broadcast_flushes)*
+                #(#pending_handlers)*
+
+                break handled;
+            }
+        }
+    };
+    input.items.push(syn::parse2(process_pending_method)?);
+    let run_until_idle_method = quote! {
+        /// Process the pending requests until there are none left, yielding in between, so that
+        /// the clients polled alongside can follow up with further requests.
+        pub async fn run_until_idle(&mut self) {
+            while self.process_pending().await > 0 {
+                let mut yielded = false;
+                core::future::poll_fn(|cx| {
+                    if yielded {
+                        return core::task::Poll::Ready(());
+                    }
+                    yielded = true;
+                    cx.waker().wake_by_ref();
+
+                    core::task::Poll::Pending
+                })
+                .await;
+            }
+        }
+    };
+    input.items.push(syn::parse2(run_until_idle_method)?);
+
+    // The streams of the controller other than those of the published fields, with their names.
+    let stream_name = |name| Ident::new(name, struct_name.span());
+    let other_streams = changes_stream
+        .map(|stream| (stream, stream_name("changes")))
+        .into_iter()
+        .chain(
+            expanded_struct
+                .snapshot
+                .as_ref()
+                .and_then(|snapshot| snapshot.stream.as_ref())
+                .map(|stream| (stream, stream_name("snapshots"))),
+        )
+        .chain(
+            signals
+                .clone()
+                .map(|signal| (&signal.stream, signal.stream_name.clone())),
+        )
+        .chain(
+            events
+                .as_ref()
+                .map(|events| (&events.stream, stream_name("events"))),
+        )
+        .collect::<Vec<_>>();
+    // The client methods giving access to the streams, and reading the published fields locally.
+    let stream_methods = |channels: Option<&TokenStream>| {
+        let published_field_getters = published_fields
+            .iter()
+            .map(|field| generate_published_field_getters(field, channels));
+        let other_stream_getters = other_streams
+            .iter()
+            .map(|(stream, stream_name)| stream.getters(stream_name, channels));
+
+        quote! {
+            #(#published_field_getters)*
+
+            #(#other_stream_getters)*
+
+            /// The maximum number of subscribers of each stream.
+            pub const fn max_subscribers(&self) -> usize {
+                firmware_controller::stream::MAX_SUBSCRIBERS
+            }
+        }
+    };
+    let events_declarations = events.as_ref().map(|events| &events.declarations);
+
+    let client_methods = methods.clone().map(|m| &m.client_method);
+    let client_stream_methods = stream_methods(None);
+
+    let client_impl: ItemImpl = syn::parse2(quote! {
+        impl #client_name {
+            pub const fn new() -> Self {
+                Self
+            }
+
+            #(#client_methods)*
+
+            #(#pub_setter_client_methods)*
+
+            #(#pub_getter_client_methods)*
+
+            #client_stream_methods
+        }
+    })?;
+    let client_trait = client_trait::generate(&client_impl, &struct_name);
+    let blocking_client = args
+        .std
+        .then(|| blocking_client::generate(&client_impl, &struct_name));
+    let client_mock = if let Some(mock_cfg) = &args.mock {
+        // Only the request/response methods are mocked, the streams and local reads are served
+        // from the mock's own channels.
+        let mocked_methods = methods
+            .clone()
+            .map(|m| &m.client_method)
+            .chain(pub_setters.iter().map(|s| &s.client_method))
+            .chain(pub_getters.iter().map(|g| &g.client_method))
+            .map(|method| syn::parse2::<ImplItemFn>(method.clone()).map(|m| m.sig.ident))
+            .collect::<Result<Vec<_>>>()?;
+        let streams = published_fields
+            .iter()
+            .flat_map(|field| {
+                [
+                    Some(&field.stream),
+                    field.transitions_stream.as_ref(),
+                    field.alarm_stream.as_ref(),
+                ]
+            })
+            .flatten()
+            .chain(other_streams.iter().map(|(stream, _)| *stream))
+            .collect::<Vec<_>>();
+        let inject_methods = published_fields
+            .iter()
+            .map(|field| field.inject_method.clone())
+            .chain(signals.clone().map(|s| s.inject_method.clone()))
+            .collect::<Vec<_>>();
+
+        Some(mock::generate(
+            &client_impl,
+            &mocked_methods,
+            &streams,
+            &stream_methods(Some(&mock::channels())),
+            &inject_methods,
+            &struct_name,
+            mock_cfg,
+        ))
+    } else {
+        None
+    };
+
+    Ok(quote! {
+        #(#args_channel_declarations)*
+        #(#pub_setter_channel_declarations)*
+        #(#pub_getter_channel_declarations)*
+
+        #input
+
+        #controller_impl
+
+        // All the channels are statics, so the client doesn't need any state of its own.
+        #[derive(Debug, Clone, Copy, Default)]
+        pub struct #client_name;
+
+        #client_impl
+
+        #client_trait
+
+        #client_mock
+
+        #blocking_client
+
+        #(#signal_declarations)*
+
+        #events_declarations
+    })
+}
+
+/// Generate the client methods of a published field: its streams, local read and waits.
+///
+/// The channels are the statics, unless `channels` is given (see [`channel_ref`]). In that case,
+/// the last broadcast value is always read from the Watch channel, as the atomic is a static.
+fn generate_published_field_getters(
+    field: &PublishedFieldInfo,
+    channels: Option<&TokenStream>,
+) -> TokenStream {
+    let stream_name = |suffix| {
+        Ident::new(
+            &format!("{}_{suffix}", field.field_name),
+            field.field_name.span(),
+        )
+    };
+    let changed_getters = field.stream.getters(&stream_name("changed"), channels);
+    let transitions_getters = field
+        .transitions_stream
+        .as_ref()
+        .map(|stream| stream.getters(&stream_name("transitions"), channels));
+    let alarm_getters = field
+        .alarm_stream
+        .as_ref()
+        .map(|stream| stream.getters(&stream_name("alarm"), channels));
+    let now_method_name = Ident::new(
+        &format!("{}_now", field.field_name),
+        field.field_name.span(),
+    );
+    let field_type = &field.field_type;
+    let watch_channel = channel_ref(&field.watch_channel_name, channels);
+    // Reads the atomic or Watch directly, without a round-trip to the controller.
+    let now_value = match (&field.atomic_load, channels) {
+        (Some(atomic_load), None) => atomic_load.clone(),
+        _ => quote! { embassy_sync::watch::Watch::try_get(#watch_channel) },
+    };
+    let wait_for_method_name = Ident::new(
+        &format!("wait_for_{}", field.field_name),
+        field.field_name.span(),
+    );
+    let wait_for_eq_method_name = Ident::new(
+        &format!("wait_for_{}_eq", field.field_name),
+        field.field_name.span(),
+    );
+    let subscribe = field.stream.subscribe(channels);
+    // The subscriber, and hence the subscriber slot, is only held while waiting.
+    let wait_for_methods = quote! {
+        pub async fn #wait_for_method_name(
+            &self,
+            predicate: impl Fn(&#field_type) -> bool,
+        ) -> #field_type {
+            if let Some(value) =
+                embassy_sync::watch::Watch::try_get_and(#watch_channel, &predicate)
+            {
+                return value;
+            }
+            let mut subscriber = #subscribe;
+
+            subscriber.wait_for(predicate).await
+        }
+
+        pub async fn #wait_for_eq_method_name<V>(&self, value: V) -> #field_type
+        where
+            #field_type: core::cmp::PartialEq<V>,
+        {
+            self.#wait_for_method_name(|v| *v == value).await
+        }
+    };
+    quote! {
+        #changed_getters
+
+        pub fn #now_method_name(&self) -> Option<#field_type> {
+            #now_value
+        }
+
+        #wait_for_methods
+
+        #transitions_getters
+
+        #alarm_getters
+    }
+}
+
+/// Whether any method of the impl is marked as atomic (`#[controller(atomic)]`).
+pub(crate) fn has_atomic_methods(input: &ItemImpl) -> bool {
+    input.items.iter().any(|item| match item {
+        ImplItem::Fn(m) => m.attrs.iter().any(|attr| {
+            attr.path().is_ident("controller")
+                && attr.parse_args::<Ident>().is_ok_and(|i| i == "atomic")
+        }),
+        _ => false,
+    })
+}
+
+/// Split each atomic method into a wrapper, which defers the broadcasts of published fields
+/// until the method returns, and the original method under a new name.
+///
+/// Returns the new names of the original methods.
+fn split_atomic_methods(input: &mut ItemImpl, struct_name: &Ident) -> Result<Vec<Ident>> {
+    let (depth_field_name, flush_method_name) = batch_idents(struct_name);
+    let mut batched_methods = Vec::new();
+    let mut wrappers = Vec::new();
+
+    for item in &mut input.items {
+        let ImplItem::Fn(method) = item else {
+            continue;
+        };
+        if !remove_atomic_attr(method)? {
+            continue;
+        }
+        match method.sig.inputs.first() {
+            Some(syn::FnArg::Receiver(receiver))
+                if receiver.reference.is_some() && receiver.mutability.is_some() => {}
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    &method.sig,
+                    "atomic methods must take `&mut self`",
+                ))
+            }
+        }
+
+        let mut wrapper = method.clone();
+        let batched_name = Ident::new(
+            &format!("{}_in_batch", method.sig.ident),
+            method.sig.ident.span(),
+        );
+        method.sig.ident = batched_name.clone();
+        method.vis = Visibility::Inherited;
+
+        let in_names = MethodInputArgs::parse(&wrapper)?.names;
+        for arg in &mut wrapper.sig.inputs {
+            if let syn::FnArg::Typed(arg) = arg {
+                if let syn::Pat::Ident(pat) = &mut *arg.pat {
+                    pat.mutability = None;
+                }
+            }
+        }
+        let await_call = wrapper.sig.asyncness.map(|_| quote! { .await });
+        wrapper.block = parse_quote!({
+            self.#depth_field_name += 1;
+            let ret = self.#batched_name(#(#in_names),*) #await_call;
+            self.#depth_field_name -= 1;
+            if self.#depth_field_name == 0 {
+                self.#flush_method_name();
+            }
+
+            ret
+        });
+
+        batched_methods.push(batched_name);
+        wrappers.push(ImplItem::Fn(wrapper));
+    }
+    input.items.extend(wrappers);
+
+    Ok(batched_methods)
+}
+
+/// Remove the `#[controller(atomic)]` attribute from a method, returning whether it was present.
+fn remove_atomic_attr(method: &mut ImplItemFn) -> Result<bool> {
+    let mut atomic = false;
+    for attr in &method.attrs {
+        if !attr.path().is_ident("controller") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("atomic") {
+                atomic = true;
+
+                Ok(())
+            } else {
+                let e = format!(
+                    "expected `atomic`, found `{}`",
+                    meta.path.get_ident().unwrap()
+                );
+
+                Err(syn::Error::new_spanned(meta.path, e))
+            }
+        })?;
+    }
+    method
+        .attrs
+        .retain(|attr| !attr.path().is_ident("controller"));
+
+    Ok(atomic)
+}
+
+fn get_methods(
+    input: &mut ItemImpl,
+    struct_name: &Ident,
+    unproxied_methods: &[Ident],
+) -> Result<Vec<Method>> {
+    input
+        .items
+        .iter_mut()
+        .filter_map(|item| match item {
+            // Hooks and the original atomic methods are only called by the generated code, so
+            // they're not proxied.
+            syn::ImplItem::Fn(m) if unproxied_methods.contains(&m.sig.ident) => None,
+            syn::ImplItem::Fn(m) => Some(ProxiedMethod::parse(m, struct_name).map(Method::Proxied)),
+            syn::ImplItem::Verbatim(tokens) => {
+                // … thus parse them ourselves and construct an ImplItemFn from that
+                let ImplItemSignal { attrs, vis, sig } =
+                    match syn::parse2::<ImplItemSignal>(tokens.clone()) {
+                        Ok(decl) => decl,
+                        Err(e) => return Some(Err(e)),
+                    };
+                *item = ImplItem::Fn(ImplItemFn {
+                    attrs,
+                    vis,
+                    defaultness: None,
+                    sig,
+                    // This empty block will be replaced below.
+                    block: parse_quote!({}),
+                });
+                match item {
+                    ImplItem::Fn(m) => Some(Signal::parse(m, struct_name).map(Method::Signal)),
+                    _ => unreachable!(),
+                }
+            }
+            _ => None,
+        })
+        .collect::<Result<Vec<_>>>()
+}
+
+fn get_struct_name(input: &ItemImpl) -> Result<Ident> {
+    match input.self_ty.as_ref() {
+        syn::Type::Path(path) => {
+            let path = &path.path;
+            if path.segments.len() != 1 {
+                return Err(syn::Error::new(
+                    path.span(),
+                    "Expected single segment in type path",
+                ));
+            }
+            let segment = path.segments.first().unwrap();
+
+            Ok(segment.ident.clone())
+        }
+        _ => Err(syn::Error::new(
+            input.self_ty.span(),
+            "Expected type path in impl block",
+        )),
+    }
+}
+
+#[derive(Debug)]
+enum Method {
+    /// A method that will be proxied.
+    Proxied(ProxiedMethod),
+    /// A signal method.
+    Signal(Signal),
+}
+
+/// Handling by the controller of the requests received from the client on a channel.
+#[derive(Debug)]
+struct RequestHandler {
+    /// Names of the channels of the requests and of the replies.
+    channel_names: [Ident; 2],
+    /// Name of the receiver of the requests.
+    receiver_name: Ident,
+    /// The pattern binding the received request.
+    pattern: TokenStream,
+    /// The handling of the request, including the reply to the client.
+    body: TokenStream,
+}
+
+impl RequestHandler {
+    /// The select! arm waiting for a request and handling it.
+    fn select_arm(&self) -> TokenStream {
+        let Self {
+            receiver_name,
+            pattern,
+            body,
+            ..
+        } = self;
+
+        quote! {
+            #pattern = futures::FutureExt::fuse(
+                embassy_sync::channel::Receiver::receive(&#receiver_name),
+            ) => {
+                #body
+            }
+        }
+    }
+
+    /// Handle a request if there's one pending, and restart the enclosing loop.
+    fn try_handle(&self) -> TokenStream {
+        let Self {
+            receiver_name,
+            pattern,
+            body,
+            ..
+        } = self;
+
+        quote! {
+            if let core::result::Result::Ok(#pattern) =
+                embassy_sync::channel::Receiver::try_receive(&#receiver_name)
+            {
+                #body
+
+                handled += 1;
+                continue;
+            }
+        }
+    }
+
+    /// Drop the requests and replies left in the channels.
+    fn clear(&self) -> TokenStream {
+        let [request_channel_name, reply_channel_name] = &self.channel_names;
+
+        quote! {
+            embassy_sync::channel::Channel::clear(&#request_channel_name);
+            embassy_sync::channel::Channel::clear(&#reply_channel_name);
+        }
+    }
+}
+
+/// Method that will be called by the client.
+// TODO: Better name.
+#[derive(Debug)]
+struct ProxiedMethod {
+    /// The arguments' channel declarations (both for input and output).
+    args_channel_declarations: TokenStream,
+    /// The input arguments' receiver & output sender declarations.
+    args_channels_rx_tx: TokenStream,
+    /// The handler proxying the method call from the input channel & method return to the output
+    /// channel.
+    handler: RequestHandler,
+    /// The client-side method.
+    client_method: TokenStream,
+}
+
+impl ProxiedMethod {
+    fn parse(method: &ImplItemFn, struct_name: &Ident) -> Result<Self> {
+        let method_args = ProxiedMethodArgs::parse(method)?;
+
+        let (args_channel_declarations, input_channel_name, output_channel_name) =
+            method_args.generate_args_channel_declarations(struct_name);
+        let (args_channels_rx_tx, handler) =
+            method_args.generate_args_channel_rx_tx(&input_channel_name, &output_channel_name);
+        let client_method =
+            method_args.generate_client_method(&input_channel_name, &output_channel_name);
+
+        Ok(Self {
+            args_channel_declarations,
+            args_channels_rx_tx,
+            handler,
+            client_method,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ProxiedMethodArgs<'a> {
+    method: &'a ImplItemFn,
+    in_args: MethodInputArgs,
+    out_type: TokenStream,
+}
+
+impl ProxiedMethodArgs<'_> {
+    fn parse(method: &ImplItemFn) -> Result<ProxiedMethodArgs<'_>> {
+        let in_args = MethodInputArgs::parse(method)?;
+        let out_type = match &method.sig.output {
+            syn::ReturnType::Type(_, ty) => quote! { #ty },
+            syn::ReturnType::Default => quote! { () },
+        };
+
+        Ok(ProxiedMethodArgs {
+            method,
+            in_args,
+            out_type,
+        })
+    }
+
+    fn generate_args_channel_declarations(
+        &self,
+        struct_name: &Ident,
+    ) -> (TokenStream, Ident, Ident) {
+        let in_types = &self.in_args.types;
+        let out_type = &self.out_type;
+        let method_name = &self.method.sig.ident;
+        let method_name_str = method_name.to_string();
+
+        let struct_name_caps = struct_name.to_string().to_uppercase();
+        let method_name_caps = method_name_str.to_uppercase();
+        let input_channel_name = Ident::new(
+            &format!("{struct_name_caps}_{method_name_caps}_INPUT_CHANNEL"),
+            self.method.span(),
+        );
+        let output_channel_name = Ident::new(
+            &format!("{struct_name_caps}_{method_name_caps}_OUTPUT_CHANNEL"),
+            self.method.span(),
+        );
+        let capacity = super::ALL_CHANNEL_CAPACITY;
+        let args_channel_declarations = quote! {
+            static #input_channel_name:
+                embassy_sync::channel::Channel<
+                    embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                    (#(#in_types),*),
+                    #capacity,
+                > = embassy_sync::channel::Channel::new();
+            static #output_channel_name:
+                embassy_sync::channel::Channel<
+                    embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                    #out_type,
+                    #capacity,
+                > = embassy_sync::channel::Channel::new();
+        };
+
+        (
+            args_channel_declarations,
+            input_channel_name,
+            output_channel_name,
+        )
+    }
+
+    // Also generates the handler for the method dispatch.
+    fn generate_args_channel_rx_tx(
+        &self,
+        input_channel_name: &Ident,
+        output_channel_name: &Ident,
+    ) -> (TokenStream, RequestHandler) {
+        let in_names = &self.in_args.names;
+        let method_name = &self.method.sig.ident;
+        let method_name_str = method_name.to_string();
+        let input_channel_rx_name =
+            Ident::new(&format!("{method_name_str}_rx"), self.method.span());
+        let output_channel_tx_name =
+            Ident::new(&format!("{method_name_str}_tx"), self.method.span());
+        let args_channels_rx_tx = quote! {
+            let #input_channel_rx_name = embassy_sync::channel::Channel::receiver(&#input_channel_name);
+            let #output_channel_tx_name = embassy_sync::channel::Channel::sender(&#output_channel_name);
+        };
+        let handler = RequestHandler {
+            channel_names: [input_channel_name.clone(), output_channel_name.clone()],
+            receiver_name: input_channel_rx_name,
+            pattern: quote! { (#(#in_names),*) },
+            body: quote! {
+                let ret = self.#method_name(#(#in_names),*).await;
+
+                embassy_sync::channel::Sender::send(&#output_channel_tx_name, ret).await;
+            },
+        };
+
+        (args_channels_rx_tx, handler)
+    }
+
+    fn generate_client_method(
+        &self,
+        input_channel_name: &Ident,
+        output_channel_name: &Ident,
+    ) -> TokenStream {
+        let in_names = &self.in_args.names;
+        let in_names = if in_names.is_empty() {
+            quote! { () }
+        } else {
+            quote! { (#(#in_names),*) }
+        };
+        let mut method = self.method.clone();
+
+        method.block = parse_quote!({
+            // Method call.
+            embassy_sync::channel::Channel::send(&#input_channel_name, #in_names).await;
+
+            // Method return.
+            embassy_sync::channel::Channel::receive(&#output_channel_name).await
+        });
+
+        quote! { #method }
+    }
+}
+
+#[derive(Debug)]
+struct Signal {
+    /// The input arguments' channel and client-side struct declarations.
+    declarations: TokenStream,
+    /// The name of the signal's stream, as used in the client's stream getters.
+    stream_name: Ident,
+    /// Stream of the signal's arguments.
+    stream: ClientStream,
+    /// Name of the variant in the events enum (e.g., PowerError).
+    event_variant_name: Ident,
+    /// Name of the args struct (e.g., ControllerPowerErrorArgs).
+    args_struct_name: Ident,
+    /// The mock method emitting the signal on the mock's channels (e.g., inject_power_error).
+    inject_method: TokenStream,
+    /// For sticky signals, the reset of the last emitted value.
+    last_reset: Option<TokenStream>,
+}
+
+impl Signal {
+    fn parse(method: &mut ImplItemFn, struct_name: &Ident) -> Result<Self> {
+        let attrs = remove_signal_attr(method)?;
+
+        let MethodInputArgs { types, names } = MethodInputArgs::parse(method)?;
+
+        let method_name = &method.sig.ident;
+        let method_name_str = method_name.to_string();
+        let struct_name_caps = struct_name.to_string().to_uppercase();
+        let method_name_caps = method_name_str.to_uppercase();
+        let method_name_pascal = snake_to_pascal_case(&method_name_str);
+        let signal_channel_name = Ident::new(
+            &format!("{struct_name_caps}_{method_name_caps}_CHANNEL"),
+            method.span(),
+        );
+        let signal_publisher_name = Ident::new(
+            &format!("{struct_name_caps}_{method_name_caps}_PUBLISHER"),
+            method.span(),
+        );
+        let signal_last_name = Ident::new(
+            &format!("{struct_name_caps}_{method_name_caps}_LAST"),
+            method.span(),
+        );
+        let args_struct_name = Ident::new(
+            &format!("{struct_name}{method_name_pascal}Args"),
+            method.span(),
+        );
+
+        let event_variant_name = Ident::new(&method_name_pascal, method.span());
+        let (events_channel_name, events_publisher_name, event_enum_name) =
+            events_idents(struct_name);
+
+        // Sticky signals keep the last emitted value around, so that it can be handed to
+        // subscribers that come after the fact. The value is stored and published in the same
+        // critical section as subscribers subscribe in, so that they get it either way but never
+        // both. Hence, it's published without waiting for lagging subscribers.
+        let mock_channels = mock::channels();
+        let mock_signal_channel = channel_ref(&signal_channel_name, Some(&mock_channels));
+        let mock_events_channel = channel_ref(&events_channel_name, Some(&mock_channels));
+        let (last_declaration, signal_publish, signal_inject) = if attrs.sticky {
+            let mock_last = channel_ref(&signal_last_name, Some(&mock_channels));
+            (
+                quote! {
+                    static #signal_last_name:
+                        firmware_controller::__private::SignalLast<#args_struct_name> =
+                        embassy_sync::blocking_mutex::Mutex::new(core::cell::RefCell::new(None));
+                },
+                quote! {
+                    embassy_sync::blocking_mutex::Mutex::lock(&#signal_last_name, |last| {
+                        *last.borrow_mut() = Some(core::clone::Clone::clone(&args));
+                        embassy_sync::pubsub::publisher::Pub::publish_immediate(publisher, args);
+                    });
+                },
+                quote! {
+                    embassy_sync::blocking_mutex::Mutex::lock(#mock_last, |last| {
+                        *last.borrow_mut() = Some(core::clone::Clone::clone(&args));
+                        embassy_sync::pubsub::PubSubChannel::immediate_publisher(
+                            #mock_signal_channel,
+                        )
+                        .publish_immediate(args);
+                    });
+                },
+            )
+        } else {
+            (
+                quote! {},
+                quote! {
+                    embassy_sync::pubsub::publisher::Pub::publish(publisher, args).await;
+                },
+                quote! {
+                    embassy_sync::pubsub::PubSubChannel::immediate_publisher(#mock_signal_channel)
+                        .publish_immediate(args);
+                },
+            )
+        };
+        let stream = ClientStream::signal(
+            &parse_quote!(#args_struct_name),
+            &signal_channel_name,
+            attrs.sticky.then_some(&signal_last_name),
+        );
+        let subscriber_declaration = &stream.declaration;
+
+        let declarations = quote! {
+            static #signal_channel_name:
+                firmware_controller::__private::SignalChannel<#args_struct_name> =
+                firmware_controller::__private::SignalChannel::new();
+
+            static #signal_publisher_name: embassy_sync::once_lock::OnceLock<
+                firmware_controller::__private::SignalPublisher<#args_struct_name>,
+            > = embassy_sync::once_lock::OnceLock::new();
+
+            #last_declaration
+
+            #[derive(Debug, Clone)]
+            pub struct #args_struct_name {
+                #(pub #names: #types),*
+            }
+
+            #subscriber_declaration
+        };
+
+        let last_reset = attrs.sticky.then(|| {
+            quote! {
+                embassy_sync::blocking_mutex::Mutex::lock(&#signal_last_name, |last| {
+                    *last.borrow_mut() = None;
+                });
+            }
+        });
+
+        let inject_method_name = Ident::new(&format!("inject_{method_name_str}"), method.span());
+        let inject_method = quote! {
+            pub fn #inject_method_name(&self, #(#names: #types),*) {
+                let args = #args_struct_name { #(#names),* };
+
+                embassy_sync::pubsub::PubSubChannel::immediate_publisher(#mock_events_channel)
+                    .publish_immediate(#event_enum_name::#event_variant_name(
+                        core::clone::Clone::clone(&args),
+                    ));
+                #signal_inject
+            }
+        };
+
+        method.block = parse_quote!({
+            let publisher = embassy_sync::once_lock::OnceLock::get_or_init(
+                &#signal_publisher_name,
+                // Safety: The publisher is only initialized once.
+                || embassy_sync::pubsub::PubSubChannel::publisher(&#signal_channel_name).unwrap());
+            let args = #args_struct_name { #(#names),* };
+
+            let events_publisher = embassy_sync::once_lock::OnceLock::get_or_init(
+                &#events_publisher_name,
+                // Safety: The publisher is only initialized once.
+                || embassy_sync::pubsub::PubSubChannel::publisher(&#events_channel_name).unwrap());
+            embassy_sync::pubsub::publisher::Pub::publish(
+                events_publisher,
+                #event_enum_name::#event_variant_name(core::clone::Clone::clone(&args)),
+            ).await;
+
+            #signal_publish
+        });
+
+        Ok(Self {
+            declarations,
+            stream_name: method_name.clone(),
+            stream,
+            event_variant_name,
+            args_struct_name,
+            inject_method,
+            last_reset,
+        })
+    }
+}
+
+/// Generated code for the stream of all signals of a controller.
+#[derive(Debug)]
+struct Events {
+    /// The events enum, channel and subscribers declarations.
+    declarations: TokenStream,
+    /// Stream of the events.
+    stream: ClientStream,
+}
+
+/// Names of the events channel, its publisher and the events enum.
+fn events_idents(struct_name: &Ident) -> (Ident, Ident, Ident) {
+    let struct_name_caps = struct_name.to_string().to_uppercase();
+
+    (
+        Ident::new(
+            &format!("{struct_name_caps}_EVENTS_CHANNEL"),
+            struct_name.span(),
+        ),
+        Ident::new(
+            &format!("{struct_name_caps}_EVENTS_PUBLISHER"),
+            struct_name.span(),
+        ),
+        Ident::new(&format!("{struct_name}Event"), struct_name.span()),
+    )
+}
+
+/// Generate the enum and stream covering all signals. Returns `None` if there are no signals.
+fn generate_events<'a>(
+    signals: impl Iterator<Item = &'a Signal>,
+    struct_name: &Ident,
+) -> Option<Events> {
+    let (variants, args): (Vec<_>, Vec<_>) = signals
+        .map(|s| (&s.event_variant_name, &s.args_struct_name))
+        .unzip();
+    if variants.is_empty() {
+        return None;
+    }
+
+    let (events_channel_name, events_publisher_name, event_enum_name) = events_idents(struct_name);
+    let stream = ClientStream::signal(&parse_quote!(#event_enum_name), &events_channel_name, None);
+    let subscriber_declaration = &stream.declaration;
+
+    let declarations = quote! {
+        static #events_channel_name: firmware_controller::__private::SignalChannel<#event_enum_name> =
+            firmware_controller::__private::SignalChannel::new();
+
+        static #events_publisher_name: embassy_sync::once_lock::OnceLock<
+            firmware_controller::__private::SignalPublisher<#event_enum_name>,
+        > = embassy_sync::once_lock::OnceLock::new();
+
+        // Not every controller has a consumer for the combined stream.
+        #[allow(dead_code)]
+        #[derive(Debug, Clone)]
+        pub enum #event_enum_name {
+            #(#variants(#args)),*
+        }
+
+        #subscriber_declaration
+    };
+
+    Some(Events {
+        declarations,
+        stream,
+    })
+}
+
+// Like ImplItemFn, but with a semicolon at the end instead of a body block
+struct ImplItemSignal {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    sig: Signature,
+}
+
+impl Parse for ImplItemSignal {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let sig = input.parse()?;
+        let _: Token![;] = input.parse()?;
+
+        Ok(ImplItemSignal { attrs, vis, sig })
+    }
+}
+
+/// Parsed `#[controller(signal(...))]` attributes of a signal method.
+#[derive(Debug, Default)]
+struct SignalAttrs {
+    /// Whether the last emitted value is replayed to new subscribers.
+    sticky: bool,
+}
+
+fn remove_signal_attr(method: &mut ImplItemFn) -> syn::Result<SignalAttrs> {
+    let mut signal_attrs = SignalAttrs::default();
+    method.attrs = method
+        .attrs
+        .iter()
+        .cloned()
+        .filter_map(|attr| {
+            if !attr.path().is_ident("controller") {
+                return Some(Ok(attr));
+            }
+
+            let res = attr.parse_nested_meta(|meta| {
+                if !meta.path.is_ident("signal") {
+                    let e = format!(
+                        "expected `signal` attribute, found `{}`",
+                        meta.path.get_ident().unwrap()
+                    );
+
+                    return Err(syn::Error::new_spanned(meta.path, e));
+                }
+
+                if meta.input.peek(syn::token::Paren) {
+                    meta.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("sticky") {
+                            signal_attrs.sticky = true;
+
+                            Ok(())
+                        } else {
+                            let e = format!(
+                                "expected `sticky`, found `{}`",
+                                meta.path.get_ident().unwrap()
+                            );
+
+                            Err(syn::Error::new_spanned(meta.path, e))
+                        }
+                    })?;
+                }
+
+                Ok(())
+            });
+            match res {
+                Err(e) => Some(Err(e)),
+                Ok(()) => None,
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(signal_attrs)
+}
+
+#[derive(Debug)]
+struct MethodInputArgs {
+    types: Vec<TokenStream>,
+    names: Vec<TokenStream>,
+}
+
+impl MethodInputArgs {
+    fn parse(method: &ImplItemFn) -> Result<Self> {
+        let (in_types, in_names): (Vec<_>, Vec<_>) = method
+            .sig
+            .inputs
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::FnArg::Typed(arg) => {
+                    let arg_type = &arg.ty;
+                    let arg_name = match &*arg.pat {
+                        syn::Pat::Ident(pat) => &pat.ident,
+                        _ => {
+                            return Some(Err(syn::Error::new(
+                                arg.span(),
+                                "Expected identifier as argument name",
+                            )))
+                        }
+                    };
+
+                    Some(Ok((quote! { #arg_type }, quote! { #arg_name })))
+                }
+                // Filter `self`.
+                syn::FnArg::Receiver(_) => None,
+            })
+            .collect::<Result<Vec<_>>>()?
+            .into_iter()
+            .unzip();
+
+        Ok(Self {
+            types: in_types,
+            names: in_names,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct PubSetter {
+    channel_declarations: TokenStream,
+    rx_tx: TokenStream,
+    handler: RequestHandler,
+    client_method: TokenStream,
+}
+
+#[derive(Debug)]
+struct PubGetter {
+    channel_declarations: TokenStream,
+    rx_tx: TokenStream,
+    handler: RequestHandler,
+    client_method: TokenStream,
+}
+
+fn generate_pub_setter(
+    field: &SetterFieldInfo,
+    struct_name: &Ident,
+    input: &ItemImpl,
+) -> Result<PubSetter> {
+    let field_name = &field.field_name;
+    let field_type = &field.field_type;
+    let setter_method_name = &field.setter_name;
+    let field_name_str = field_name.to_string();
+
+    // With a validator, the client gets back whatever the validator returns.
+    let validator_return_type = field
+        .validate
+        .as_ref()
+        .map(|validate| validator_return_type(validate, input))
+        .transpose()?;
+    // Same with a range that rejects values out of it.
+    let rejected_range = field.range.as_ref().filter(|range| !range.clamp);
+    let out_of_range_name = out_of_range_ident(struct_name);
+    let (output_type, client_return_type) = match (&validator_return_type, rejected_range) {
+        (Some(ty), _) => (quote! { #ty }, quote! { -> #ty }),
+        (None, Some(_)) => {
+            let ty = quote! { core::result::Result<(), #out_of_range_name<#field_type>> };
+            (ty.clone(), quote! { -> #ty })
+        }
+        (None, None) => (quote! { () }, quote! {}),
+    };
+
+    let struct_name_caps = struct_name.to_string().to_uppercase();
+    let field_name_caps = field_name_str.to_uppercase();
+    let input_channel_name = Ident::new(
+        &format!("{}_SET_{}_INPUT_CHANNEL", struct_name_caps, field_name_caps),
+        field_name.span(),
+    );
+    let output_channel_name = Ident::new(
+        &format!(
+            "{}_SET_{}_OUTPUT_CHANNEL",
+            struct_name_caps, field_name_caps
+        ),
+        field_name.span(),
+    );
+    let capacity = super::ALL_CHANNEL_CAPACITY;
+
+    let channel_declarations = quote! {
+        static #input_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                #field_type,
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+        static #output_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                #output_type,
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+    };
+
+    let input_channel_rx_name =
+        Ident::new(&format!("{}_value_rx", field_name_str), field_name.span());
+    let output_channel_tx_name =
+        Ident::new(&format!("{}_ack_tx", field_name_str), field_name.span());
+    let rx_tx = quote! {
+        let #input_channel_rx_name = embassy_sync::channel::Channel::receiver(&#input_channel_name);
+        let #output_channel_tx_name = embassy_sync::channel::Channel::sender(&#output_channel_name);
+    };
+
+    let update = if let Some(internal_setter) = &field.internal_setter_name {
+        // Published field: call the internal setter which broadcasts changes.
+        quote! {
+            self.#internal_setter(value).await;
+        }
+    } else {
+        // Non-published field: set the field directly.
+        let (assignment, on_change_call) = match &field.on_change {
+            Some(on_change) => (
+                quote! { let old = core::mem::replace(&mut self.#field_name, value); },
+                quote! {
+                    self.#on_change(old, core::clone::Clone::clone(&self.#field_name)).await;
+                },
+            ),
+            None => (quote! { self.#field_name = value; }, quote! {}),
+        };
+        let derived_updates = &field.derived_updates;
+        quote! {
+            #assignment
+            #(self.#derived_updates().await;)*
+            #on_change_call
+        }
+    };
+    // Clamped before validation. The internal setter of a published field clamps again, for the
+    // values set by the controller itself.
+    let clamp = field
+        .range
+        .as_ref()
+        .filter(|range| range.clamp)
+        .map(|range| clamp_code(&range.const_name));
+    let update_and_reply = match (&field.validate, rejected_range) {
+        // The internal setter of a published field checks the range itself.
+        (None, Some(_)) if field.internal_setter_name.is_some() => {
+            let internal_setter = &field.internal_setter_name;
+            quote! {
+                let result = self.#internal_setter(value).await;
+
+                embassy_sync::channel::Sender::send(&#output_channel_tx_name, result).await;
+            }
+        }
+        (None, Some(range)) => {
+            let range_const = &range.const_name;
+            quote! {
+                if core::ops::RangeInclusive::contains(&Self::#range_const, &value) {
+                    #update
+
+                    embassy_sync::channel::Sender::send(
+                        &#output_channel_tx_name,
+                        core::result::Result::Ok(()),
+                    ).await;
+                } else {
+                    embassy_sync::channel::Sender::send(
+                        &#output_channel_tx_name,
+                        core::result::Result::Err(#out_of_range_name(value)),
+                    ).await;
+                }
+            }
+        }
+        (Some(validate), _) => quote! {
+            let result = self.#validate(&value);
+            if core::result::Result::is_ok(&result) {
+                #update
+            }
+
+            embassy_sync::channel::Sender::send(&#output_channel_tx_name, result).await;
+        },
+        (None, None) => quote! {
+            #update
+
+            embassy_sync::channel::Sender::send(&#output_channel_tx_name, ()).await;
+        },
+    };
+    let handler = RequestHandler {
+        channel_names: [input_channel_name.clone(), output_channel_name.clone()],
+        receiver_name: input_channel_rx_name,
+        pattern: quote! { value },
+        body: quote! {
+            #clamp
+            #update_and_reply
+        },
+    };
+
+    let client_method = quote! {
+        pub async fn #setter_method_name(&self, value: #field_type) #client_return_type {
+            embassy_sync::channel::Channel::send(&#input_channel_name, value).await;
+            embassy_sync::channel::Channel::receive(&#output_channel_name).await
+        }
+    };
+
+    Ok(PubSetter {
+        channel_declarations,
+        rx_tx,
+        handler,
+        client_method,
+    })
+}
+
+/// Looks up the return type of the validator method in the controller impl.
+fn validator_return_type(validate: &Ident, input: &ItemImpl) -> Result<syn::Type> {
+    let method = input
+        .items
+        .iter()
+        .find_map(|item| match item {
+            ImplItem::Fn(m) if m.sig.ident == *validate => Some(m),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            syn::Error::new_spanned(
+                validate,
+                format!(
+                    "validator method `{}` not found in the impl block",
+                    validate
+                ),
+            )
+        })?;
+
+    match &method.sig.output {
+        syn::ReturnType::Type(_, ty) => Ok((**ty).clone()),
+        syn::ReturnType::Default => Err(syn::Error::new_spanned(
+            &method.sig,
+            "validator methods must return a `Result`",
+        )),
+    }
+}
+
+fn generate_snapshot_getter(snapshot: &SnapshotInfo, struct_name: &Ident) -> PubGetter {
+    let snapshot_struct_name = &snapshot.struct_name;
+    let take_method_name = &snapshot.take_method_name;
+
+    let struct_name_caps = struct_name.to_string().to_uppercase();
+    let input_channel_name = Ident::new(
+        &format!("{struct_name_caps}_SNAPSHOT_INPUT_CHANNEL"),
+        struct_name.span(),
+    );
+    let output_channel_name = Ident::new(
+        &format!("{struct_name_caps}_SNAPSHOT_OUTPUT_CHANNEL"),
+        struct_name.span(),
+    );
+    let capacity = super::ALL_CHANNEL_CAPACITY;
+
+    let channel_declarations = quote! {
+        static #input_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                (),
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+        static #output_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                #snapshot_struct_name,
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+    };
+
+    let rx_tx = quote! {
+        let snapshot_request_rx = embassy_sync::channel::Channel::receiver(&#input_channel_name);
+        let snapshot_response_tx = embassy_sync::channel::Channel::sender(&#output_channel_name);
+    };
+
+    // All fields are copied in a single dispatch, so the snapshot is consistent.
+    let handler = RequestHandler {
+        channel_names: [input_channel_name.clone(), output_channel_name.clone()],
+        receiver_name: Ident::new("snapshot_request_rx", struct_name.span()),
+        pattern: quote! { _ },
+        body: quote! {
+            let snapshot = self.#take_method_name();
+
+            embassy_sync::channel::Sender::send(&snapshot_response_tx, snapshot).await;
+        },
+    };
+
+    let client_method = quote! {
+        pub async fn snapshot(&self) -> #snapshot_struct_name {
+            embassy_sync::channel::Channel::send(&#input_channel_name, ()).await;
+            embassy_sync::channel::Channel::receive(&#output_channel_name).await
+        }
+    };
+
+    PubGetter {
+        channel_declarations,
+        rx_tx,
+        handler,
+        client_method,
+    }
+}
+
+fn generate_pub_getter(field: &GetterFieldInfo, struct_name: &Ident) -> PubGetter {
+    let field_name = &field.field_name;
+    let field_type = &field.field_type;
+    let getter_name = &field.getter_name;
+    let field_name_str = field_name.to_string();
+
+    let struct_name_caps = struct_name.to_string().to_uppercase();
+    let field_name_caps = field_name_str.to_uppercase();
+    let input_channel_name = Ident::new(
+        &format!("{}_GET_{}_INPUT_CHANNEL", struct_name_caps, field_name_caps),
+        field_name.span(),
+    );
+    let output_channel_name = Ident::new(
+        &format!(
+            "{}_GET_{}_OUTPUT_CHANNEL",
+            struct_name_caps, field_name_caps
+        ),
+        field_name.span(),
+    );
+    let capacity = super::ALL_CHANNEL_CAPACITY;
+
+    let channel_declarations = quote! {
+        static #input_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                (),
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+        static #output_channel_name:
+            embassy_sync::channel::Channel<
+                embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex,
+                #field_type,
+                #capacity,
+            > = embassy_sync::channel::Channel::new();
+    };
+
+    let input_channel_rx_name = Ident::new(
+        &format!("{}_get_request_rx", field_name_str),
+        field_name.span(),
+    );
+    let output_channel_tx_name = Ident::new(
+        &format!("{}_get_response_tx", field_name_str),
+        field_name.span(),
+    );
+    let rx_tx = quote! {
+        let #input_channel_rx_name = embassy_sync::channel::Channel::receiver(&#input_channel_name);
+        let #output_channel_tx_name = embassy_sync::channel::Channel::sender(&#output_channel_name);
+    };
+
+    let handler = RequestHandler {
+        channel_names: [input_channel_name.clone(), output_channel_name.clone()],
+        receiver_name: input_channel_rx_name,
+        pattern: quote! { _ },
+        body: quote! {
+            let value = core::clone::Clone::clone(&self.#field_name);
+
+            embassy_sync::channel::Sender::send(&#output_channel_tx_name, value).await;
+        },
+    };
+
+    let client_method = quote! {
+        pub async fn #getter_name(&self) -> #field_type {
+            embassy_sync::channel::Channel::send(&#input_channel_name, ()).await;
+            embassy_sync::channel::Channel::receive(&#output_channel_name).await
+        }
+    };
+
+    PubGetter {
+        channel_declarations,
+        rx_tx,
+        handler,
+        client_method,
+    }
+}
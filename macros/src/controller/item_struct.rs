@@ -0,0 +1,1745 @@
+use crate::controller::mock;
+use crate::controller::subscription::{channel_ref, ClientStream};
+use crate::util::*;
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{
+    meta::ParseNestedMeta, spanned::Spanned, Field, Fields, Ident, ItemStruct, LitInt, LitStr,
+    Result, Token,
+};
+
+/// Information about a published field, to be used by impl processing.
+#[derive(Debug, Clone)]
+pub(crate) struct PublishedFieldInfo {
+    pub field_name: Ident,
+    pub field_type: syn::Type,
+    /// Name of the Watch channel holding the last broadcast value.
+    pub watch_channel_name: Ident,
+    /// Stream of the values of the field.
+    pub stream: ClientStream,
+    /// Stream of the transitions of the field, if transitions are enabled.
+    pub transitions_stream: Option<ClientStream>,
+    /// If the broadcast is rate-limited, the pending broadcast it schedules.
+    pub pending_broadcast: Option<PendingBroadcastInfo>,
+    /// Stream of the alarm of the field, if it has one.
+    pub alarm_stream: Option<ClientStream>,
+    /// Expression loading the last broadcast value from its atomic, if the field has one.
+    pub atomic_load: Option<TokenStream>,
+    /// Reset of the atomic to its state before the controller is built, if the field has one.
+    pub atomic_reset: Option<TokenStream>,
+    /// The mock method broadcasting a value to the field's streams on the mock's channels (e.g.,
+    /// inject_state).
+    pub inject_method: TokenStream,
+}
+
+/// Information about the pending broadcast of a rate-limited published field.
+#[derive(Debug, Clone)]
+pub(crate) struct PendingBroadcastInfo {
+    /// The field holding the deadline of the pending broadcast, if any.
+    pub deadline_field_name: Ident,
+    /// The method to call once the deadline is reached.
+    pub flush_method_name: Ident,
+}
+
+/// Information about a field with a getter, to be used by impl processing.
+#[derive(Debug, Clone)]
+pub(crate) struct GetterFieldInfo {
+    pub field_name: Ident,
+    pub field_type: syn::Type,
+    pub getter_name: Ident,
+}
+
+/// Information about a field with a public setter, to be used by impl processing.
+#[derive(Debug, Clone)]
+pub(crate) struct SetterFieldInfo {
+    pub field_name: Ident,
+    pub field_type: syn::Type,
+    /// The public setter method name (client API).
+    pub setter_name: Ident,
+    /// If the field is published, the internal setter name to call. Otherwise None.
+    pub internal_setter_name: Option<Ident>,
+    /// The method to call with the old and new values after an update, if any.
+    ///
+    /// Published fields call it from the internal setter.
+    pub on_change: Option<Ident>,
+    /// The method to validate values set through the client with, if any.
+    pub validate: Option<Ident>,
+    /// The range to enforce on values set through the client, if any.
+    ///
+    /// Published fields also enforce it in the internal setter.
+    pub range: Option<RangeInfo>,
+    /// Methods updating the derived fields computed from this field.
+    ///
+    /// Published fields call them from the internal setter.
+    pub derived_updates: Vec<Ident>,
+}
+
+/// Information about the range of a field, to be used by impl processing.
+#[derive(Debug, Clone)]
+pub(crate) struct RangeInfo {
+    /// Name of the associated const holding the range.
+    pub const_name: Ident,
+    /// Whether out-of-range values are clamped into the range, instead of rejected.
+    pub clamp: bool,
+}
+
+/// Information about the snapshot of the fields, to be used by impl processing.
+#[derive(Debug, Clone)]
+pub(crate) struct SnapshotInfo {
+    pub struct_name: Ident,
+    /// Stream of the snapshots, if enabled.
+    pub stream: Option<ClientStream>,
+    /// The controller method creating the snapshot.
+    pub take_method_name: Ident,
+}
+
+/// Result of expanding a struct.
+pub(crate) struct ExpandedStruct {
+    pub tokens: TokenStream,
+    pub published_fields: Vec<PublishedFieldInfo>,
+    pub getter_fields: Vec<GetterFieldInfo>,
+    pub setter_fields: Vec<SetterFieldInfo>,
+    /// Stream of the changes of all published fields, if there are any.
+    pub changes_stream: Option<ClientStream>,
+    /// Methods of the controller referenced by field attributes (e.g., `on_change`, `validate`).
+    pub hook_methods: Vec<Ident>,
+    /// The snapshot of the published and getter fields, if there are any.
+    pub snapshot: Option<SnapshotInfo>,
+}
+
+/// Expand the controller struct.
+///
+/// If `batching` is set, broadcasts can be deferred until the end of an atomic method. If
+/// `snapshot_stream` is set, the snapshots of the fields are broadcast along with the published
+/// fields.
+pub(crate) fn expand(
+    mut input: ItemStruct,
+    batching: bool,
+    snapshot_stream: bool,
+) -> Result<ExpandedStruct> {
+    let struct_name = &input.ident;
+
+    let struct_fields =
+        StructFields::parse(&mut input.fields, struct_name, batching, snapshot_stream)?;
+    let field_names = struct_fields.names().collect::<Vec<_>>();
+
+    // Collect published field info.
+    let (
+        watch_channel_declarations,
+        sender_fields_declarations,
+        sender_fields_initializations,
+        setters,
+        subscriber_declarations,
+        published_fields_info,
+    ) = struct_fields.published().fold(
+        (quote!(), quote!(), quote!(), quote!(), quote!(), Vec::new()),
+        |(
+            watch_channels,
+            sender_fields_declarations,
+            sender_fields_initializations,
+            setters,
+            subscribers,
+            mut infos,
+        ),
+         f| {
+            let published = f.published.as_ref().unwrap();
+            let (watch_channel, sender_field, sender_field_init, setter, subscriber) = (
+                &published.watch_channel_declaration,
+                &published.sender_field_declaration,
+                &published.sender_field_initialization,
+                &published.setter,
+                &published.subscriber_declaration,
+            );
+
+            infos.push(published.info.clone());
+
+            (
+                quote! { #watch_channels #watch_channel },
+                quote! { #sender_fields_declarations #sender_field, },
+                quote! { #sender_fields_initializations #sender_field_init, },
+                quote! { #setters #setter },
+                quote! { #subscribers #subscriber },
+                infos,
+            )
+        },
+    );
+
+    let batching_code = batching.then(|| {
+        let (depth_field_name, flush_method_name) = batch_idents(struct_name);
+        let batch_flushes = struct_fields
+            .published()
+            .filter_map(|f| f.published.as_ref().unwrap().batch_flush.as_ref());
+        (
+            quote! { #depth_field_name: usize, },
+            quote! { #depth_field_name: 0, },
+            quote! {
+                fn #flush_method_name(&mut self) {
+                    #(#batch_flushes)*
+                }
+            },
+        )
+    });
+    let (batch_field_declaration, batch_field_initialization, batch_flush) = match batching_code {
+        Some((declaration, initialization, flush)) => (declaration, initialization, flush),
+        None => (quote!(), quote!(), quote!()),
+    };
+
+    // Collect getter field info.
+    let getter_fields_info: Vec<GetterFieldInfo> = struct_fields
+        .with_getter()
+        .map(|f| {
+            let field_name = f.field.ident.as_ref().unwrap().clone();
+            let field_type = f.field.ty.clone();
+            let getter_name = f.attrs.getter_name.clone().unwrap();
+            GetterFieldInfo {
+                field_name,
+                field_type,
+                getter_name,
+            }
+        })
+        .collect();
+
+    // Collect setter field info.
+    let setter_fields_info: Vec<SetterFieldInfo> = struct_fields
+        .with_setter()
+        .map(|f| {
+            let field_name = f.field.ident.as_ref().unwrap().clone();
+            let field_type = f.field.ty.clone();
+            // Use explicit setter name if provided, otherwise default to set_<field_name>.
+            let setter_name =
+                f.attrs.setter_name.clone().unwrap_or_else(|| {
+                    Ident::new(&format!("set_{}", field_name), field_name.span())
+                });
+            // If published, use the internal setter; otherwise set field directly.
+            let internal_setter_name = if f.attrs.publish {
+                Some(Ident::new(
+                    &format!("set_{}", field_name),
+                    field_name.span(),
+                ))
+            } else {
+                None
+            };
+            let range = f.attrs.range.as_ref().map(|_| RangeInfo {
+                const_name: range_const_ident(&field_name),
+                clamp: f.attrs.clamp,
+            });
+            SetterFieldInfo {
+                field_name,
+                field_type,
+                setter_name,
+                internal_setter_name,
+                on_change: f.attrs.on_change.clone(),
+                validate: f.attrs.validate.clone(),
+                range,
+                derived_updates: f.derived_updates.clone(),
+            }
+        })
+        .collect();
+
+    let hook_methods = struct_fields
+        .fields
+        .iter()
+        .flat_map(|f| {
+            [
+                f.attrs.on_change.clone(),
+                f.attrs.validate.clone(),
+                f.attrs.derived.as_ref().map(|d| d.with.clone()),
+            ]
+        })
+        .flatten()
+        .collect();
+
+    let alarm_enum_declaration = struct_fields
+        .published()
+        .any(|f| f.attrs.alarm.is_some())
+        .then(|| generate_alarm_enum(struct_name));
+
+    let out_of_range_declaration = struct_fields
+        .with_setter()
+        .any(|f| f.attrs.range.is_some() && !f.attrs.clamp)
+        .then(|| generate_out_of_range_struct(struct_name));
+    let range_and_unit_consts = struct_fields
+        .fields
+        .iter()
+        .map(generate_range_and_unit_consts);
+
+    let changes = generate_changes_code(&struct_fields, struct_name);
+    let changes_declarations = changes.as_ref().map(|(declarations, _)| declarations);
+    let changes_stream = changes.as_ref().map(|(_, stream)| stream.clone());
+
+    let snapshot = generate_snapshot_code(&struct_fields, struct_name, snapshot_stream);
+    let snapshot_declarations = snapshot.as_ref().map(|s| &s.declarations);
+    let snapshot_methods = snapshot.as_ref().map(|s| &s.methods);
+    let initial_snapshot_send = snapshot_stream.then(|| {
+        let (_, _, broadcast_method_name) = snapshot_idents(struct_name);
+        quote! { __self.#broadcast_method_name(); }
+    });
+
+    let fields = struct_fields.raw_fields().collect::<Vec<_>>();
+    // Generate function parameters without visibility (visibility is only valid on struct fields).
+    // Derived fields are computed instead.
+    let new_fn_params = struct_fields
+        .fields
+        .iter()
+        .filter(|f| f.attrs.derived.is_none())
+        .map(|f| {
+            let ident = f.field.ident.as_ref().unwrap();
+            let ty = &f.field.ty;
+            quote! { #ident: #ty }
+        });
+    let derived_initializations = struct_fields.derived().map(|f| {
+        let field_name = f.field.ident.as_ref().unwrap();
+        let derived = f.attrs.derived.as_ref().unwrap();
+        let with = &derived.with;
+        let from = &derived.from;
+        quote! {
+            let #field_name = Self::#with(#(&#from),*);
+        }
+    });
+    let derived_updates = struct_fields.derived().map(generate_derived_update);
+    let vis = &input.vis;
+
+    // Generate initial value sends for Watch channels.
+    let initial_value_sends = struct_fields
+        .published()
+        .map(|f| &f.published.as_ref().unwrap().initial_value_send);
+
+    Ok(ExpandedStruct {
+        tokens: quote! {
+            #vis struct #struct_name {
+                #(#fields),*,
+                #sender_fields_declarations
+                #batch_field_declaration
+            }
+
+            impl #struct_name {
+                #[allow(clippy::too_many_arguments)]
+                pub fn new(#(#new_fn_params),*) -> Self {
+                    #(#derived_initializations)*
+                    // Hidden fields first, as their initialization may need the field values.
+                    let __self = Self {
+                        #sender_fields_initializations
+                        #batch_field_initialization
+                        #(#field_names),*,
+                    };
+                    // Send initial values so subscribers can get them immediately.
+                    #(#initial_value_sends)*
+                    #initial_snapshot_send
+                    __self
+                }
+
+                #(#range_and_unit_consts)*
+
+                #setters
+
+                #batch_flush
+
+                #(#derived_updates)*
+
+                #snapshot_methods
+            }
+
+            #watch_channel_declarations
+
+            #subscriber_declarations
+
+            #changes_declarations
+
+            #alarm_enum_declaration
+
+            #out_of_range_declaration
+
+            #snapshot_declarations
+        },
+        published_fields: published_fields_info,
+        getter_fields: getter_fields_info,
+        setter_fields: setter_fields_info,
+        changes_stream,
+        hook_methods,
+        snapshot: snapshot.map(|s| s.info),
+    })
+}
+
+/// Parsed controller attributes for a field.
+#[derive(Debug, Default)]
+struct ControllerAttrs {
+    /// Whether the field has `publish` attribute.
+    publish: bool,
+    /// Options of the `publish` attribute (from `publish(...)`).
+    publish_attrs: PublishAttrs,
+    /// If set, the alarm thresholds (from `alarm(...)`).
+    alarm: Option<AlarmAttrs>,
+    /// If set, the getter method name (from `getter` or `getter = "name"`).
+    getter_name: Option<Ident>,
+    /// If set, the setter method name (from `setter` or `setter = "name"`).
+    setter_name: Option<Ident>,
+    /// If set, the method to call after every update (from `on_change = "name"`).
+    on_change: Option<Ident>,
+    /// If set, the method to validate client-set values with (from `validate = "name"`).
+    validate: Option<Ident>,
+    /// If set, the inclusive range of valid values (from `range = <start>..=<end>`).
+    range: Option<syn::ExprRange>,
+    /// Whether to clamp out-of-range values instead of rejecting them (from `clamp`).
+    clamp: bool,
+    /// If set, the unit of the field's values (from `unit = "name"`).
+    unit: Option<LitStr>,
+    /// If set, how the field is computed from other fields (from `derived(...)`).
+    derived: Option<DerivedAttrs>,
+}
+
+/// Parsed options of the `publish` attribute.
+#[derive(Debug, Default)]
+struct PublishAttrs {
+    /// Whether to skip broadcasting values equal to the current one (from `distinct`).
+    distinct: bool,
+    /// Whether to also broadcast `(previous, new)` pairs (from `transitions`).
+    transitions: bool,
+    /// Rate limiting of the broadcast, if any.
+    rate_limit: Option<RateLimit>,
+    /// Whether to also keep the last broadcast value in an atomic (from `atomic`).
+    atomic: bool,
+}
+
+/// Parsed options of the `alarm` attribute.
+#[derive(Debug)]
+struct AlarmAttrs {
+    /// The alarm is raised when the value goes above this threshold (from `above = <value>`).
+    above: syn::Expr,
+    /// The alarm is cleared when the value goes below this threshold (from
+    /// `clear_below = <value>`). If not set, it's cleared as soon as the value is no longer above
+    /// the `above` threshold.
+    clear_below: Option<syn::Expr>,
+}
+
+/// Parsed options of the `derived` attribute.
+#[derive(Debug)]
+struct DerivedAttrs {
+    /// The fields the value is computed from (from `from = [<field>, ...]`).
+    from: Vec<Ident>,
+    /// The associated function computing the value from references to the source fields (from
+    /// `with = "name"`).
+    with: Ident,
+}
+
+/// Rate limiting of the broadcast of a published field.
+#[derive(Debug)]
+enum RateLimit {
+    /// Broadcast at most once per interval, delaying the last value of an interval to its end
+    /// (from `min_interval_ms = <ms>`).
+    MinInterval(LitInt),
+    /// Broadcast only once the value has been stable for the given time (from
+    /// `debounce_ms = <ms>`).
+    Debounce(LitInt),
+}
+
+/// Parsed struct fields.
+#[derive(Debug)]
+struct StructFields {
+    fields: Vec<StructField>,
+}
+
+impl StructFields {
+    /// Parse the fields of the struct.
+    fn parse(
+        fields: &mut Fields,
+        struct_name: &Ident,
+        batching: bool,
+        snapshot_stream: bool,
+    ) -> Result<Self> {
+        let fields = match fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter_mut()
+                .map(|field| Ok((parse_controller_attrs(field)?, field.clone())))
+                .collect::<Result<Vec<_>>>()?,
+            Fields::Unnamed(_) | Fields::Unit => {
+                return Err(syn::Error::new_spanned(
+                    fields,
+                    "controller struct must have only named fields",
+                ))
+            }
+        };
+
+        // Derived fields are computed from the other fields, so those need to know about them.
+        let derived = fields
+            .iter()
+            .filter_map(|(attrs, field)| {
+                let field_name = field.ident.clone().unwrap();
+                attrs.derived.as_ref().map(|d| (d.from.clone(), field_name))
+            })
+            .collect::<Vec<_>>();
+        for (from, derived_field_name) in &derived {
+            for source in from {
+                let source_attrs = fields
+                    .iter()
+                    .find(|(_, field)| field.ident.as_ref() == Some(source))
+                    .map(|(attrs, _)| attrs);
+                match source_attrs {
+                    None => {
+                        return Err(syn::Error::new_spanned(
+                            source,
+                            format!("no field named `{source}` to derive from"),
+                        ))
+                    }
+                    Some(attrs) if attrs.derived.is_some() => {
+                        return Err(syn::Error::new_spanned(
+                            source,
+                            "derived fields can't be derived from other derived fields",
+                        ))
+                    }
+                    // Only the setters recompute the derived fields, so the value would never be
+                    // updated after `new`.
+                    Some(attrs) if !attrs.publish && attrs.setter_name.is_none() => {
+                        return Err(syn::Error::new_spanned(
+                            source,
+                            format!(
+                                "derived fields can only be derived from fields with a setter, \
+                                 `{source}` must be `publish` or have a `setter`"
+                            ),
+                        ))
+                    }
+                    Some(_) => (),
+                }
+            }
+            if from.is_empty() {
+                return Err(syn::Error::new_spanned(
+                    derived_field_name,
+                    "derived fields must be derived from at least one field",
+                ));
+            }
+        }
+
+        let fields = fields
+            .into_iter()
+            .map(|(attrs, field)| {
+                let field_name = field.ident.as_ref().unwrap();
+                let derived_updates = derived
+                    .iter()
+                    .filter(|(from, _)| from.contains(field_name))
+                    .map(|(_, derived_field_name)| derived_update_ident(derived_field_name))
+                    .collect();
+                StructField::new(
+                    field,
+                    attrs,
+                    struct_name,
+                    derived_updates,
+                    batching,
+                    snapshot_stream,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { fields })
+    }
+
+    /// Names of all the fields.
+    fn names(&self) -> impl Iterator<Item = &syn::Ident> {
+        self.fields.iter().map(|f| f.field.ident.as_ref().unwrap())
+    }
+
+    /// All raw fields.
+    fn raw_fields(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().map(|f| &f.field)
+    }
+
+    /// All the published fields.
+    fn published(&self) -> impl Iterator<Item = &StructField> {
+        self.fields.iter().filter(|f| f.published.is_some())
+    }
+
+    /// All fields with getters.
+    fn with_getter(&self) -> impl Iterator<Item = &StructField> {
+        self.fields.iter().filter(|f| f.attrs.getter_name.is_some())
+    }
+
+    /// All fields with setters (via `setter` attribute).
+    fn with_setter(&self) -> impl Iterator<Item = &StructField> {
+        self.fields.iter().filter(|f| f.attrs.setter_name.is_some())
+    }
+
+    /// All derived fields.
+    fn derived(&self) -> impl Iterator<Item = &StructField> {
+        self.fields.iter().filter(|f| f.attrs.derived.is_some())
+    }
+}
+
+/// A struct field with its parsed controller attributes and generated code.
+#[derive(Debug)]
+struct StructField {
+    /// The field with controller attributes removed.
+    field: Field,
+    /// Parsed controller attributes.
+    attrs: ControllerAttrs,
+    /// Generated publish code (if `publish` attribute is present).
+    published: Option<PublishedFieldCode>,
+    /// Methods updating the derived fields computed from this field.
+    derived_updates: Vec<Ident>,
+}
+
+impl StructField {
+    /// Create a struct field from its parsed controller attributes.
+    fn new(
+        field: Field,
+        attrs: ControllerAttrs,
+        struct_name: &Ident,
+        derived_updates: Vec<Ident>,
+        batching: bool,
+        snapshot_stream: bool,
+    ) -> Result<Self> {
+        if attrs.derived.is_some() && attrs.setter_name.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "derived fields can't have a setter",
+            ));
+        }
+        let published = if attrs.publish {
+            Some(generate_publish_code(
+                &field,
+                struct_name,
+                &attrs,
+                &derived_updates,
+                batching,
+                snapshot_stream,
+            )?)
+        } else if attrs.alarm.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`alarm` can only be used on published fields",
+            ));
+        } else {
+            None
+        };
+        if attrs.on_change.is_some() && !attrs.publish && attrs.setter_name.is_none() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`on_change` can only be used on published fields or fields with a setter",
+            ));
+        }
+        if attrs.validate.is_some() && attrs.setter_name.is_none() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`validate` can only be used on fields with a setter",
+            ));
+        }
+        if attrs.clamp && attrs.range.is_none() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`clamp` can only be used together with `range`",
+            ));
+        }
+        if attrs.range.is_some() && !attrs.clamp && attrs.derived.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`range` can't reject values of a derived field, use `clamp`",
+            ));
+        }
+        if attrs.range.is_some() && !attrs.clamp && attrs.validate.is_some() {
+            return Err(syn::Error::new_spanned(
+                field,
+                "`range` can't reject values of a field with a validator, use `clamp` or check \
+                 the range in the validator",
+            ));
+        }
+
+        Ok(Self {
+            field,
+            attrs,
+            published,
+            derived_updates,
+        })
+    }
+}
+
+/// Generated code for a published field.
+#[derive(Debug)]
+struct PublishedFieldCode {
+    /// Watch sender field declaration, preceded by the broadcast state fields (if any).
+    sender_field_declaration: proc_macro2::TokenStream,
+    /// Watch sender field initialization, preceded by the broadcast state fields (if any).
+    sender_field_initialization: proc_macro2::TokenStream,
+    /// Field setter and broadcast helpers.
+    setter: proc_macro2::TokenStream,
+    /// Watch channel declaration.
+    watch_channel_declaration: proc_macro2::TokenStream,
+    /// Declaration of the subscribers of the stream.
+    subscriber_declaration: proc_macro2::TokenStream,
+    /// Broadcast of the value if it was set during a batch (if batching is enabled).
+    batch_flush: Option<proc_macro2::TokenStream>,
+    /// Initial value send in `new`, where the controller is named `__self`.
+    initial_value_send: proc_macro2::TokenStream,
+    /// Information to be passed to impl processing.
+    info: PublishedFieldInfo,
+}
+
+/// Parse the `#[controller(...)]` attributes from a field.
+fn parse_controller_attrs(field: &mut Field) -> Result<ControllerAttrs> {
+    let mut attrs = ControllerAttrs::default();
+
+    let Some(attr) = field
+        .attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("controller"))
+    else {
+        return Ok(attrs);
+    };
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("publish") {
+            attrs.publish = true;
+            if meta.input.peek(syn::token::Paren) {
+                attrs.publish_attrs = parse_publish_attrs(&meta)?;
+            }
+        } else if meta.path.is_ident("alarm") {
+            attrs.alarm = Some(parse_alarm_attrs(&meta)?);
+        } else if meta.path.is_ident("getter") {
+            let field_name = field.ident.as_ref().unwrap();
+            if meta.input.peek(Token![=]) {
+                meta.input.parse::<Token![=]>()?;
+                let name: LitStr = meta.input.parse()?;
+                attrs.getter_name = Some(Ident::new(&name.value(), name.span()));
+            } else {
+                attrs.getter_name = Some(field_name.clone());
+            }
+        } else if meta.path.is_ident("setter") {
+            let field_name = field.ident.as_ref().unwrap();
+            if meta.input.peek(Token![=]) {
+                meta.input.parse::<Token![=]>()?;
+                let name: LitStr = meta.input.parse()?;
+                attrs.setter_name = Some(Ident::new(&name.value(), name.span()));
+            } else {
+                let default_name = format!("set_{}", field_name);
+                attrs.setter_name = Some(Ident::new(&default_name, field_name.span()));
+            }
+        } else if meta.path.is_ident("on_change") {
+            let name: LitStr = meta.value()?.parse()?;
+            attrs.on_change = Some(Ident::new(&name.value(), name.span()));
+        } else if meta.path.is_ident("validate") {
+            let name: LitStr = meta.value()?.parse()?;
+            attrs.validate = Some(Ident::new(&name.value(), name.span()));
+        } else if meta.path.is_ident("range") {
+            attrs.range = Some(parse_range(&meta)?);
+        } else if meta.path.is_ident("clamp") {
+            attrs.clamp = true;
+        } else if meta.path.is_ident("unit") {
+            attrs.unit = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("derived") {
+            attrs.derived = Some(parse_derived_attrs(&meta)?);
+        } else {
+            let ident = meta.path.get_ident().unwrap();
+            let e = format!(
+                "expected `publish`, `alarm`, `getter`, `setter`, `on_change`, `validate`, \
+                 `range`, `clamp`, `unit`, or `derived`, found `{}`",
+                ident
+            );
+            return Err(syn::Error::new_spanned(ident, e));
+        }
+
+        Ok(())
+    })?;
+
+    // Derived fields are always published, as that's the only way to observe them.
+    if attrs.derived.is_some() {
+        attrs.publish = true;
+    }
+
+    // Remove controller attributes from the field.
+    field
+        .attrs
+        .retain(|attr| !attr.path().is_ident("controller"));
+
+    Ok(attrs)
+}
+
+/// Parse the options of the `publish(...)` attribute.
+fn parse_publish_attrs(meta: &ParseNestedMeta<'_>) -> Result<PublishAttrs> {
+    let mut attrs = PublishAttrs::default();
+
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("distinct") {
+            attrs.distinct = true;
+        } else if meta.path.is_ident("transitions") {
+            attrs.transitions = true;
+        } else if meta.path.is_ident("atomic") {
+            attrs.atomic = true;
+        } else if meta.path.is_ident("min_interval_ms") || meta.path.is_ident("debounce_ms") {
+            if attrs.rate_limit.is_some() {
+                return Err(meta.error(
+                    "only one of `min_interval_ms` and `debounce_ms` can be specified",
+                ));
+            }
+            let ms: LitInt = meta.value()?.parse()?;
+            attrs.rate_limit = Some(if meta.path.is_ident("min_interval_ms") {
+                RateLimit::MinInterval(ms)
+            } else {
+                RateLimit::Debounce(ms)
+            });
+        } else {
+            let ident = meta.path.get_ident().unwrap();
+            let e = format!(
+                "expected `distinct`, `transitions`, `atomic`, `min_interval_ms`, or `debounce_ms`, \
+                 found `{}`",
+                ident
+            );
+            return Err(syn::Error::new_spanned(ident, e));
+        }
+
+        Ok(())
+    })?;
+
+    Ok(attrs)
+}
+
+/// Parse the options of the `alarm(...)` attribute.
+fn parse_alarm_attrs(meta: &ParseNestedMeta<'_>) -> Result<AlarmAttrs> {
+    let mut above = None;
+    let mut clear_below = None;
+
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("above") {
+            above = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("clear_below") {
+            clear_below = Some(meta.value()?.parse()?);
+        } else {
+            let ident = meta.path.get_ident().unwrap();
+            let e = format!("expected `above` or `clear_below`, found `{}`", ident);
+            return Err(syn::Error::new_spanned(ident, e));
+        }
+
+        Ok(())
+    })?;
+
+    let above = above.ok_or_else(|| meta.error("`alarm` requires an `above` threshold"))?;
+    if let Some(clear_below) = &clear_below {
+        // The thresholds can only be compared here if they're literals.
+        if let (Some(above_value), Some(clear_below_value)) =
+            (literal_value(&above), literal_value(clear_below))
+        {
+            if clear_below_value > above_value {
+                return Err(syn::Error::new_spanned(
+                    clear_below,
+                    "`clear_below` can't be greater than `above`",
+                ));
+            }
+        }
+    }
+
+    Ok(AlarmAttrs { above, clear_below })
+}
+
+/// The value of a (possibly negated) numeric literal, if the expression is one.
+fn literal_value(expr: &syn::Expr) -> Option<f64> {
+    match expr {
+        syn::Expr::Lit(syn::ExprLit { lit, .. }) => match lit {
+            syn::Lit::Int(lit) => lit.base10_parse().ok(),
+            syn::Lit::Float(lit) => lit.base10_parse().ok(),
+            _ => None,
+        },
+        syn::Expr::Unary(syn::ExprUnary {
+            op: syn::UnOp::Neg(_),
+            expr,
+            ..
+        }) => literal_value(expr).map(|value| -value),
+        syn::Expr::Paren(syn::ExprParen { expr, .. }) => literal_value(expr),
+        _ => None,
+    }
+}
+
+/// Parse the options of the `derived(...)` attribute.
+fn parse_derived_attrs(meta: &ParseNestedMeta<'_>) -> Result<DerivedAttrs> {
+    let mut from = None;
+    let mut with = None;
+
+    meta.parse_nested_meta(|meta| {
+        if meta.path.is_ident("from") {
+            let value = meta.value()?;
+            let content;
+            syn::bracketed!(content in value);
+            let fields =
+                content.parse_terminated(<Ident as syn::parse::Parse>::parse, Token![,])?;
+            from = Some(fields.into_iter().collect());
+        } else if meta.path.is_ident("with") {
+            let name: LitStr = meta.value()?.parse()?;
+            with = Some(Ident::new(&name.value(), name.span()));
+        } else {
+            let ident = meta.path.get_ident().unwrap();
+            let e = format!("expected `from` or `with`, found `{}`", ident);
+            return Err(syn::Error::new_spanned(ident, e));
+        }
+
+        Ok(())
+    })?;
+
+    let from = from.ok_or_else(|| meta.error("`derived` requires the `from` fields"))?;
+    let with = with.ok_or_else(|| meta.error("`derived` requires a `with` function"))?;
+
+    Ok(DerivedAttrs { from, with })
+}
+
+fn derived_update_ident(field_name: &Ident) -> Ident {
+    Ident::new(&format!("update_{field_name}"), field_name.span())
+}
+
+/// Generate the method recomputing a derived field, to be called when a source field changes.
+fn generate_derived_update(field: &StructField) -> TokenStream {
+    let field_name = field.field.ident.as_ref().unwrap();
+    let derived = field.attrs.derived.as_ref().unwrap();
+    let with = &derived.with;
+    let from = &derived.from;
+    let update_name = derived_update_ident(field_name);
+    let setter_name = Ident::new(&format!("set_{field_name}"), field_name.span());
+
+    quote! {
+        async fn #update_name(&mut self) {
+            let value = Self::#with(#(&self.#from),*);
+            self.#setter_name(value).await;
+        }
+    }
+}
+
+/// Generate code for a published field using Watch channel.
+///
+/// If `snapshot_stream` is set, the broadcasts also broadcast the snapshot.
+fn generate_publish_code(
+    field: &Field,
+    struct_name: &Ident,
+    controller_attrs: &ControllerAttrs,
+    derived_updates: &[Ident],
+    batching: bool,
+    snapshot_stream: bool,
+) -> Result<PublishedFieldCode> {
+    let attrs = &controller_attrs.publish_attrs;
+    let alarm = controller_attrs.alarm.as_ref();
+    let on_change = controller_attrs.on_change.as_ref();
+    let struct_name_str = struct_name.to_string();
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+    let ty = &field.ty;
+
+    let struct_name_caps = pascal_to_snake_case(&struct_name_str).to_ascii_uppercase();
+    let field_name_caps = field_name_str.to_ascii_uppercase();
+    let watch_channel_name = Ident::new(
+        &format!("{struct_name_caps}_{field_name_caps}_WATCH"),
+        field.span(),
+    );
+
+    let field_name_pascal = snake_to_pascal_case(&field_name_str);
+    let stream = ClientStream::published(ty, &watch_channel_name);
+
+    let setter_name = Ident::new(&format!("set_{field_name_str}"), field.span());
+    let sender_name = Ident::new(&format!("{field_name_str}_sender"), field.span());
+    let (changes_channel_name, change_enum_name) = changes_idents(struct_name);
+    let change_variant_name = Ident::new(&field_name_pascal, field.span());
+
+    let broadcast_name = Ident::new(&format!("broadcast_{field_name_str}"), field.span());
+    let snapshot_broadcast = snapshot_stream.then(|| {
+        let (_, _, broadcast_snapshot_name) = snapshot_idents(struct_name);
+        quote! { self.#broadcast_snapshot_name(); }
+    });
+    let deadline_name = Ident::new(
+        &format!("{field_name_str}_broadcast_deadline"),
+        field.span(),
+    );
+    let last_broadcast_name = Ident::new(&format!("{field_name_str}_last_broadcast"), field.span());
+    let flush_name = Ident::new(&format!("flush_{field_name_str}_broadcast"), field.span());
+
+    // Rate-limited fields keep track of their pending broadcast.
+    let (rate_limit_field_declarations, rate_limit_field_initializations) = match &attrs.rate_limit
+    {
+        Some(RateLimit::MinInterval(_)) => (
+            quote! {
+                #deadline_name: Option<embassy_time::Instant>,
+                #last_broadcast_name: Option<embassy_time::Instant>,
+            },
+            quote! {
+                #deadline_name: None,
+                #last_broadcast_name: None,
+            },
+        ),
+        Some(RateLimit::Debounce(_)) => (
+            quote! { #deadline_name: Option<embassy_time::Instant>, },
+            quote! { #deadline_name: None, },
+        ),
+        None => (quote! {}, quote! {}),
+    };
+
+    // Batched fields keep track of whether their broadcast was deferred.
+    let batched_name = Ident::new(&format!("{field_name_str}_broadcast_batched"), field.span());
+    let (batch_field_declaration, batch_field_initialization) = if batching {
+        (
+            quote! { #batched_name: bool, },
+            quote! { #batched_name: false, },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    let alarm = alarm.map(|alarm| generate_alarm_code(field, struct_name, alarm));
+    let atomic = attrs
+        .atomic
+        .then(|| generate_atomic_code(field, struct_name))
+        .transpose()?;
+    let atomic_store = atomic.as_ref().map(|a| &a.store);
+    let atomic_initial_store = atomic.as_ref().map(|a| &a.initial_store);
+    let alarm_field_declaration = alarm.as_ref().map(|a| &a.field_declaration);
+    let alarm_field_initialization = alarm.as_ref().map(|a| &a.field_initialization);
+    let alarm_check = alarm.as_ref().map(|a| &a.check);
+
+    let sender_field_declaration = quote! {
+        #alarm_field_declaration
+        #rate_limit_field_declarations
+        #batch_field_declaration
+        #sender_name: firmware_controller::__private::PublishedSender<#ty>
+    };
+
+    let sender_field_initialization = quote! {
+        #alarm_field_initialization
+        #rate_limit_field_initializations
+        #batch_field_initialization
+        #sender_name: embassy_sync::watch::Watch::sender(&#watch_channel_name)
+    };
+
+    // The range is enforced on every value set, be it by the controller itself or its clients.
+    let (range_check, setter_return_type, setter_return_value) = match &controller_attrs.range {
+        Some(_) => {
+            let range_const = range_const_ident(field_name);
+            if controller_attrs.clamp {
+                (clamp_code(&range_const), quote! {}, quote! {})
+            } else {
+                let out_of_range_name = out_of_range_ident(struct_name);
+                (
+                    quote! {
+                        if !core::ops::RangeInclusive::contains(&Self::#range_const, &value) {
+                            return core::result::Result::Err(#out_of_range_name(value));
+                        }
+                    },
+                    quote! { -> core::result::Result<(), #out_of_range_name<#ty>> },
+                    quote! { core::result::Result::Ok(()) },
+                )
+            }
+        }
+        None => (quote! {}, quote! {}, quote! {}),
+    };
+
+    let distinct_check = attrs.distinct.then(|| {
+        quote! {
+            if core::cmp::PartialEq::eq(&self.#field_name, &value) {
+                return #setter_return_value;
+            }
+        }
+    });
+
+    let transitions = attrs
+        .transitions
+        .then(|| generate_transitions_code(field, struct_name));
+    // The previous value is the last one broadcast, which is not necessarily the previous value
+    // of the field if the broadcast is rate-limited.
+    let transitions_publish = transitions.as_ref().map(|transitions| {
+        let transitions_channel_name = &transitions.channel_name;
+        quote! {
+            if let Some(previous) = self.#sender_name.try_get() {
+                embassy_sync::pubsub::PubSubChannel::immediate_publisher(
+                    &#transitions_channel_name,
+                )
+                .publish_immediate((previous, core::clone::Clone::clone(&self.#field_name)));
+            }
+        }
+    });
+
+    let unbatched_broadcast = match &attrs.rate_limit {
+        Some(RateLimit::MinInterval(ms)) => quote! {
+            let now = embassy_time::Instant::now();
+            match self.#last_broadcast_name {
+                Some(last) if now < last + embassy_time::Duration::from_millis(#ms) => {
+                    // Too soon, broadcast the latest value at the end of the interval instead.
+                    self.#deadline_name = Some(last + embassy_time::Duration::from_millis(#ms));
+                }
+                _ => {
+                    self.#deadline_name = None;
+                    self.#last_broadcast_name = Some(now);
+                    self.#broadcast_name();
+                }
+            }
+        },
+        Some(RateLimit::Debounce(ms)) => quote! {
+            self.#deadline_name =
+                Some(embassy_time::Instant::now() + embassy_time::Duration::from_millis(#ms));
+        },
+        None => quote! {
+            self.#broadcast_name();
+        },
+    };
+    // During a batch, the broadcast is deferred until the end of it.
+    let (broadcast, batch_flush) = if batching {
+        let (depth_field_name, _) = batch_idents(struct_name);
+        (
+            quote! {
+                if self.#depth_field_name > 0 {
+                    self.#batched_name = true;
+                } else {
+                    #unbatched_broadcast
+                }
+            },
+            Some(quote! {
+                if self.#batched_name {
+                    self.#batched_name = false;
+                    #unbatched_broadcast
+                }
+            }),
+        )
+    } else {
+        (unbatched_broadcast, None)
+    };
+
+    let flush = attrs.rate_limit.as_ref().map(|rate_limit| {
+        let last_broadcast_update = match rate_limit {
+            RateLimit::MinInterval(_) => quote! {
+                self.#last_broadcast_name = Some(embassy_time::Instant::now());
+            },
+            RateLimit::Debounce(_) => quote! {},
+        };
+        quote! {
+            fn #flush_name(&mut self) {
+                self.#deadline_name = None;
+                #last_broadcast_update
+                self.#broadcast_name();
+            }
+        }
+    });
+
+    let (assignment, on_change_call) = match on_change {
+        Some(on_change) => (
+            quote! { let old = core::mem::replace(&mut self.#field_name, value); },
+            quote! {
+                self.#on_change(old, core::clone::Clone::clone(&self.#field_name)).await;
+            },
+        ),
+        None => (quote! { self.#field_name = value; }, quote! {}),
+    };
+
+    // Derived fields are only set by the generated code, to keep them in sync.
+    let setter_vis = if controller_attrs.derived.is_some() {
+        quote! {}
+    } else {
+        quote! { pub }
+    };
+
+    // Watch send() is sync, but we keep the setter async for API compatibility.
+    let setter = quote! {
+        #setter_vis async fn #setter_name(&mut self, value: #ty) #setter_return_type {
+            #range_check
+            #distinct_check
+            #assignment
+            #alarm_check
+            #broadcast
+            #(self.#derived_updates().await;)*
+            #on_change_call
+            #setter_return_value
+        }
+
+        fn #broadcast_name(&self) {
+            #transitions_publish
+            self.#sender_name.send(core::clone::Clone::clone(&self.#field_name));
+            #atomic_store
+            embassy_sync::pubsub::PubSubChannel::immediate_publisher(&#changes_channel_name)
+                .publish_immediate(#change_enum_name::#change_variant_name(
+                    core::clone::Clone::clone(&self.#field_name),
+                ));
+            #snapshot_broadcast
+        }
+
+        #flush
+    };
+
+    let transitions_declaration = transitions.as_ref().map(|t| &t.declarations);
+    let atomic_declaration = atomic.as_ref().map(|a| &a.declarations);
+    let alarm_declaration = alarm.as_ref().map(|a| &a.declarations);
+    let watch_channel_declaration = quote! {
+        static #watch_channel_name: firmware_controller::__private::PublishedWatch<#ty> =
+            firmware_controller::__private::PublishedWatch::new();
+
+        #transitions_declaration
+
+        #alarm_declaration
+
+        #atomic_declaration
+    };
+
+    let subscriber_declaration = stream.declaration.clone();
+
+    let mock_channels = mock::channels();
+    let mock_watch = channel_ref(&watch_channel_name, Some(&mock_channels));
+    let mock_changes = channel_ref(&changes_channel_name, Some(&mock_channels));
+    let mock_transitions_publish = transitions.as_ref().map(|transitions| {
+        let mock_transitions = channel_ref(&transitions.channel_name, Some(&mock_channels));
+        quote! {
+            if let Some(previous) = embassy_sync::watch::Watch::try_get(#mock_watch) {
+                embassy_sync::pubsub::PubSubChannel::immediate_publisher(#mock_transitions)
+                    .publish_immediate((previous, core::clone::Clone::clone(&value)));
+            }
+        }
+    });
+    let inject_method_name = Ident::new(&format!("inject_{field_name_str}"), field.span());
+    let inject_method = quote! {
+        pub fn #inject_method_name(&self, value: #ty) {
+            #mock_transitions_publish
+            embassy_sync::watch::Watch::sender(#mock_watch)
+                .send(core::clone::Clone::clone(&value));
+            embassy_sync::pubsub::PubSubChannel::immediate_publisher(#mock_changes)
+                .publish_immediate(#change_enum_name::#change_variant_name(value));
+        }
+    };
+
+    let initial_value_send = quote! {
+        __self.#sender_name.send(core::clone::Clone::clone(&__self.#field_name));
+        #atomic_initial_store
+    };
+
+    let info = PublishedFieldInfo {
+        field_name: field_name.clone(),
+        field_type: ty.clone(),
+        watch_channel_name: watch_channel_name.clone(),
+        stream,
+        transitions_stream: transitions.map(|t| t.stream),
+        pending_broadcast: attrs.rate_limit.as_ref().map(|_| PendingBroadcastInfo {
+            deadline_field_name: deadline_name,
+            flush_method_name: flush_name,
+        }),
+        alarm_stream: alarm.map(|a| a.stream),
+        atomic_load: atomic.as_ref().map(|a| a.load.clone()),
+        atomic_reset: atomic.map(|a| a.reset),
+        inject_method,
+    };
+
+    Ok(PublishedFieldCode {
+        sender_field_declaration,
+        sender_field_initialization,
+        setter,
+        watch_channel_declaration,
+        subscriber_declaration,
+        batch_flush,
+        initial_value_send,
+        info,
+    })
+}
+
+/// Generated code for the atomic holding the last broadcast value of a published field.
+struct AtomicCode {
+    /// The atomic statics.
+    declarations: TokenStream,
+    /// Store of the value in the broadcast.
+    store: TokenStream,
+    /// Store of the initial value in `new`, where the controller is named `__self`.
+    initial_store: TokenStream,
+    /// Expression loading the value, as an `Option`.
+    load: TokenStream,
+    /// Reset of the atomic statics to their initial state.
+    reset: TokenStream,
+}
+
+/// Generate the atomic holding the last broadcast value of a published field.
+///
+/// `bool`, `u8`-`u32` and `i8`-`i32` are stored as is. Any other named type (typically a fieldless
+/// enum) is stored as `u8`, and must implement `Into<u8>` and `TryFrom<u8>`.
+fn generate_atomic_code(field: &Field, struct_name: &Ident) -> Result<AtomicCode> {
+    let field_name = field.ident.as_ref().unwrap();
+    let ty = &field.ty;
+
+    let struct_name_caps = pascal_to_snake_case(&struct_name.to_string()).to_ascii_uppercase();
+    let field_name_caps = field_name.to_string().to_ascii_uppercase();
+    let atomic_name = Ident::new(
+        &format!("{struct_name_caps}_{field_name_caps}_ATOMIC"),
+        field.span(),
+    );
+    let initialized_name = Ident::new(
+        &format!("{struct_name_caps}_{field_name_caps}_ATOMIC_INITIALIZED"),
+        field.span(),
+    );
+
+    let unsupported = || {
+        syn::Error::new_spanned(
+            ty,
+            "atomic backing is only supported for `bool`, `u8`-`u32` and `i8`-`i32` fields, and \
+             for types implementing `Into<u8>` and `TryFrom<u8>` (e.g. fieldless enums)",
+        )
+    };
+    let ident = match ty {
+        syn::Type::Path(path) if path.qself.is_none() => path.path.get_ident(),
+        _ => return Err(unsupported()),
+    };
+    let primitive = match ident.map(ToString::to_string).as_deref() {
+        Some("bool") => Some("AtomicBool"),
+        Some("u8") => Some("AtomicU8"),
+        Some("u16") => Some("AtomicU16"),
+        Some("u32") => Some("AtomicU32"),
+        Some("i8") => Some("AtomicI8"),
+        Some("i16") => Some("AtomicI16"),
+        Some("i32") => Some("AtomicI32"),
+        Some(
+            "u64" | "u128" | "usize" | "i64" | "i128" | "isize" | "f32" | "f64" | "char" | "str",
+        ) => return Err(unsupported()),
+        _ => None,
+    }
+    .map(|atomic_type| Ident::new(atomic_type, ty.span()));
+    let (atomic_type, initial_value, to_atomic, from_atomic, assertion) = match &primitive {
+        Some(atomic_type) => {
+            let initial_value = if atomic_type == "AtomicBool" {
+                quote! { false }
+            } else {
+                quote! { 0 }
+            };
+            (
+                atomic_type.clone(),
+                initial_value,
+                quote! { value },
+                quote! { Some(value) },
+                quote! {},
+            )
+        }
+        None => (
+            Ident::new("AtomicU8", ty.span()),
+            quote! { 0 },
+            quote! { core::convert::Into::<u8>::into(value) },
+            // Can't fail, as the value was converted from the same type.
+            quote! { <#ty as core::convert::TryFrom<u8>>::try_from(value).ok() },
+            // Report missing conversions on the field type, rather than in the generated code.
+            quote_spanned! { ty.span() =>
+                const _: () = {
+                    fn assert_atomic_conversions<T: Into<u8> + TryFrom<u8>>() {}
+                    let _ = assert_atomic_conversions::<#ty>;
+                };
+            },
+        ),
+    };
+
+    let declarations = quote! {
+        static #atomic_name: core::sync::atomic::#atomic_type =
+            core::sync::atomic::#atomic_type::new(#initial_value);
+        static #initialized_name: core::sync::atomic::AtomicBool =
+            core::sync::atomic::AtomicBool::new(false);
+        #assertion
+    };
+    let store_from = |value: TokenStream| {
+        quote! {
+            let value = core::clone::Clone::clone(&#value);
+            #atomic_name.store(#to_atomic, core::sync::atomic::Ordering::Relaxed);
+            #initialized_name.store(true, core::sync::atomic::Ordering::Release);
+        }
+    };
+    let store = store_from(quote! { self.#field_name });
+    let initial_store = store_from(quote! { __self.#field_name });
+    let load = quote! {
+        if #initialized_name.load(core::sync::atomic::Ordering::Acquire) {
+            let value = #atomic_name.load(core::sync::atomic::Ordering::Relaxed);
+            #from_atomic
+        } else {
+            None
+        }
+    };
+
+    let reset = quote! {
+        #initialized_name.store(false, core::sync::atomic::Ordering::Release);
+        #atomic_name.store(#initial_value, core::sync::atomic::Ordering::Relaxed);
+    };
+
+    Ok(AtomicCode {
+        declarations,
+        store,
+        initial_store,
+        load,
+        reset,
+    })
+}
+
+/// Generated code for the snapshot of the fields.
+struct SnapshotCode {
+    /// Snapshot struct, Watch channel and subscribers declarations.
+    declarations: TokenStream,
+    /// Controller methods taking and broadcasting the snapshot.
+    methods: TokenStream,
+    info: SnapshotInfo,
+}
+
+/// Names of the snapshot Watch channel and of the controller methods taking and broadcasting the
+/// snapshot.
+fn snapshot_idents(struct_name: &Ident) -> (Ident, Ident, Ident) {
+    let struct_name_caps = pascal_to_snake_case(&struct_name.to_string()).to_ascii_uppercase();
+    (
+        Ident::new(
+            &format!("{struct_name_caps}_SNAPSHOT_WATCH"),
+            struct_name.span(),
+        ),
+        Ident::new("take_snapshot", struct_name.span()),
+        Ident::new("broadcast_snapshot", struct_name.span()),
+    )
+}
+
+/// Generate the snapshot of all published and getter fields, or `None` if there are none.
+///
+/// If `stream` is set, the snapshot is also broadcast whenever a published field is.
+fn generate_snapshot_code(
+    struct_fields: &StructFields,
+    struct_name: &Ident,
+    stream: bool,
+) -> Option<SnapshotCode> {
+    let (names, types): (Vec<_>, Vec<_>) = struct_fields
+        .fields
+        .iter()
+        .filter(|f| f.published.is_some() || f.attrs.getter_name.is_some())
+        .map(|f| (f.field.ident.as_ref().unwrap(), &f.field.ty))
+        .unzip();
+    if names.is_empty() {
+        return None;
+    }
+
+    let snapshot_struct_name = Ident::new(&format!("{struct_name}Snapshot"), struct_name.span());
+    let (watch_channel_name, take_method_name, broadcast_method_name) =
+        snapshot_idents(struct_name);
+    let stream = stream.then(|| {
+        ClientStream::published(
+            &syn::parse_quote!(#snapshot_struct_name),
+            &watch_channel_name,
+        )
+    });
+    // The Watch channel and the broadcast are only needed for the stream.
+    let (stream_declarations, broadcast_method) = match &stream {
+        Some(stream) => {
+            let subscriber_declaration = &stream.declaration;
+            (
+                quote! {
+                    static #watch_channel_name:
+                        firmware_controller::__private::PublishedWatch<#snapshot_struct_name> =
+                        firmware_controller::__private::PublishedWatch::new();
+
+                    #subscriber_declaration
+                },
+                quote! {
+                    fn #broadcast_method_name(&self) {
+                        embassy_sync::watch::Watch::sender(&#watch_channel_name)
+                            .send(self.#take_method_name());
+                    }
+                },
+            )
+        }
+        None => (quote! {}, quote! {}),
+    };
+
+    let declarations = quote! {
+        /// Consistent copy of the published and getter fields of the controller.
+        #[derive(Clone)]
+        pub struct #snapshot_struct_name {
+            #(pub #names: #types),*
+        }
+
+        #stream_declarations
+    };
+
+    let methods = quote! {
+        fn #take_method_name(&self) -> #snapshot_struct_name {
+            #snapshot_struct_name {
+                #(#names: core::clone::Clone::clone(&self.#names)),*
+            }
+        }
+
+        #broadcast_method
+    };
+
+    Some(SnapshotCode {
+        declarations,
+        methods,
+        info: SnapshotInfo {
+            struct_name: snapshot_struct_name,
+            stream,
+            take_method_name,
+        },
+    })
+}
+
+/// Generated code for the transitions of a published field.
+struct TransitionsCode {
+    /// Transitions channel and subscribers declarations.
+    declarations: TokenStream,
+    /// Name of the transitions channel.
+    channel_name: Ident,
+    /// Stream of the transitions.
+    stream: ClientStream,
+}
+
+/// Generate the channel and stream of `(previous, new)` pairs for a published field.
+fn generate_transitions_code(field: &Field, struct_name: &Ident) -> TransitionsCode {
+    let struct_name_str = struct_name.to_string();
+    let field_name_str = field.ident.as_ref().unwrap().to_string();
+    let ty = &field.ty;
+
+    let struct_name_caps = pascal_to_snake_case(&struct_name_str).to_ascii_uppercase();
+    let field_name_caps = field_name_str.to_ascii_uppercase();
+    let channel_name = Ident::new(
+        &format!("{struct_name_caps}_{field_name_caps}_TRANSITIONS_CHANNEL"),
+        field.span(),
+    );
+    let stream = ClientStream::signal(&syn::parse_quote!((#ty, #ty)), &channel_name, None);
+    let subscriber_declaration = &stream.declaration;
+
+    let declarations = quote! {
+        static #channel_name: firmware_controller::__private::SignalChannel<(#ty, #ty)> =
+            firmware_controller::__private::SignalChannel::new();
+
+        #subscriber_declaration
+    };
+
+    TransitionsCode {
+        declarations,
+        channel_name,
+        stream,
+    }
+}
+
+/// Generated code for the alarm of a published field.
+struct AlarmCode {
+    /// Alarm channel and subscribers declarations.
+    declarations: TokenStream,
+    /// Declaration of the field keeping track of whether the alarm is raised.
+    field_declaration: TokenStream,
+    /// Initialization of the field keeping track of whether the alarm is raised.
+    field_initialization: TokenStream,
+    /// Evaluation of the alarm, to be done by the setter after storing the value.
+    check: TokenStream,
+    /// Stream of the alarm.
+    stream: ClientStream,
+}
+
+/// Name of the alarm enum.
+fn alarm_enum_ident(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{struct_name}Alarm"), struct_name.span())
+}
+
+/// Parse the value of the `range` attribute, which must be an inclusive range with both bounds.
+fn parse_range(meta: &ParseNestedMeta) -> Result<syn::ExprRange> {
+    let range: syn::ExprRange = meta.value()?.parse()?;
+    match (&range.start, &range.limits, &range.end) {
+        (Some(_), syn::RangeLimits::Closed(_), Some(_)) => Ok(range),
+        _ => Err(syn::Error::new_spanned(
+            range,
+            "expected an inclusive range with both bounds (e.g., `0..=100`)",
+        )),
+    }
+}
+
+fn range_const_ident(field_name: &Ident) -> Ident {
+    let field_name_caps = field_name.to_string().to_ascii_uppercase();
+    Ident::new(&format!("{field_name_caps}_RANGE"), field_name.span())
+}
+
+/// Names of the batch depth field and of the method broadcasting the values set during a batch.
+pub(crate) fn batch_idents(struct_name: &Ident) -> (Ident, Ident) {
+    (
+        Ident::new("broadcast_batch_depth", struct_name.span()),
+        Ident::new("flush_batched_broadcasts", struct_name.span()),
+    )
+}
+
+/// Clamp a `value` variable into the range held by the given associated const.
+pub(crate) fn clamp_code(range_const: &Ident) -> TokenStream {
+    quote! {
+        let value = if core::cmp::PartialOrd::lt(&value, Self::#range_const.start()) {
+            core::clone::Clone::clone(Self::#range_const.start())
+        } else if core::cmp::PartialOrd::gt(&value, Self::#range_const.end()) {
+            core::clone::Clone::clone(Self::#range_const.end())
+        } else {
+            value
+        };
+    }
+}
+
+/// Name of the error returned by setters when rejecting out-of-range values.
+pub(crate) fn out_of_range_ident(struct_name: &Ident) -> Ident {
+    Ident::new(&format!("{struct_name}OutOfRange"), struct_name.span())
+}
+
+/// Generate the error for out-of-range values, shared by all ranged fields of the controller.
+fn generate_out_of_range_struct(struct_name: &Ident) -> TokenStream {
+    let out_of_range_name = out_of_range_ident(struct_name);
+
+    quote! {
+        /// The value passed to a setter was outside of the field's range.
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct #out_of_range_name<T>(pub T);
+    }
+}
+
+/// Generate the associated consts exposing the range and unit of a field (if any).
+fn generate_range_and_unit_consts(field: &StructField) -> TokenStream {
+    let field_name = field.field.ident.as_ref().unwrap();
+    let field_name_caps = field_name.to_string().to_ascii_uppercase();
+    let ty = &field.field.ty;
+
+    let range_const = field.attrs.range.as_ref().map(|range| {
+        let const_name = range_const_ident(field_name);
+        let doc = format!("Range of valid values of the `{field_name}` field.");
+        quote! {
+            #[doc = #doc]
+            pub const #const_name: core::ops::RangeInclusive<#ty> = #range;
+        }
+    });
+    let unit_const = field.attrs.unit.as_ref().map(|unit| {
+        let const_name = Ident::new(&format!("{field_name_caps}_UNIT"), field_name.span());
+        let doc = format!("Unit of the `{field_name}` field.");
+        quote! {
+            #[doc = #doc]
+            pub const #const_name: &'static str = #unit;
+        }
+    });
+
+    quote! {
+        #range_const
+        #unit_const
+    }
+}
+
+/// Generate the enum of alarm events, shared by all alarms of the controller.
+fn generate_alarm_enum(struct_name: &Ident) -> TokenStream {
+    let alarm_enum_name = alarm_enum_ident(struct_name);
+
+    quote! {
+        #[derive(Debug, Clone, PartialEq)]
+        pub enum #alarm_enum_name<T> {
+            /// The value went above the alarm threshold.
+            Raised(T),
+            /// The value went below the clearing threshold.
+            Cleared(T),
+        }
+    }
+}
+
+/// Generate the alarm evaluation and stream of a published field.
+fn generate_alarm_code(field: &Field, struct_name: &Ident, alarm: &AlarmAttrs) -> AlarmCode {
+    let struct_name_str = struct_name.to_string();
+    let field_name = field.ident.as_ref().unwrap();
+    let field_name_str = field_name.to_string();
+    let ty = &field.ty;
+
+    let struct_name_caps = pascal_to_snake_case(&struct_name_str).to_ascii_uppercase();
+    let field_name_caps = field_name_str.to_ascii_uppercase();
+    let channel_name = Ident::new(
+        &format!("{struct_name_caps}_{field_name_caps}_ALARM_CHANNEL"),
+        field.span(),
+    );
+    let raised_name = Ident::new(&format!("{field_name_str}_alarm_raised"), field.span());
+    let alarm_enum_name = alarm_enum_ident(struct_name);
+    let stream = ClientStream::signal(
+        &syn::parse_quote!(#alarm_enum_name<#ty>),
+        &channel_name,
+        None,
+    );
+    let subscriber_declaration = &stream.declaration;
+
+    let declarations = quote! {
+        static #channel_name: firmware_controller::__private::SignalChannel<#alarm_enum_name<#ty>> =
+            firmware_controller::__private::SignalChannel::new();
+
+        #subscriber_declaration
+    };
+
+    let above = &alarm.above;
+    let cleared = match &alarm.clear_below {
+        Some(clear_below) => quote! { self.#field_name < (#clear_below) },
+        None => quote! { !(self.#field_name > (#above)) },
+    };
+    let check = quote! {
+        let alarm = if !self.#raised_name && self.#field_name > (#above) {
+            self.#raised_name = true;
+            Some(#alarm_enum_name::Raised(core::clone::Clone::clone(&self.#field_name)))
+        } else if self.#raised_name && #cleared {
+            self.#raised_name = false;
+            Some(#alarm_enum_name::Cleared(core::clone::Clone::clone(&self.#field_name)))
+        } else {
+            None
+        };
+        // Like the other streams coming from the setter, don't block it on lagging subscribers.
+        if let Some(alarm) = alarm {
+            embassy_sync::pubsub::PubSubChannel::immediate_publisher(&#channel_name)
+                .publish_immediate(alarm);
+        }
+    };
+
+    AlarmCode {
+        declarations,
+        field_declaration: quote! { #raised_name: bool, },
+        // Start cleared, so that if the initial value is already above the threshold, the alarm
+        // gets raised (and the event sent) on the first update.
+        field_initialization: quote! { #raised_name: false, },
+        check,
+        stream,
+    }
+}
+
+/// Names of the changes channel and the changes enum.
+fn changes_idents(struct_name: &Ident) -> (Ident, Ident) {
+    let struct_name_str = struct_name.to_string();
+    let struct_name_caps = pascal_to_snake_case(&struct_name_str).to_ascii_uppercase();
+
+    (
+        Ident::new(
+            &format!("{struct_name_caps}_CHANGES_CHANNEL"),
+            struct_name.span(),
+        ),
+        Ident::new(&format!("{struct_name_str}Change"), struct_name.span()),
+    )
+}
+
+/// Generate the enum and stream covering changes of all published fields.
+///
+/// Returns the declarations and the stream, or `None` if there are no published fields.
+fn generate_changes_code(
+    struct_fields: &StructFields,
+    struct_name: &Ident,
+) -> Option<(TokenStream, ClientStream)> {
+    let (variants, types): (Vec<_>, Vec<_>) = struct_fields
+        .published()
+        .map(|f| {
+            let field_name = f.field.ident.as_ref().unwrap();
+            let variant = Ident::new(
+                &snake_to_pascal_case(&field_name.to_string()),
+                field_name.span(),
+            );
+
+            (variant, &f.field.ty)
+        })
+        .unzip();
+    if variants.is_empty() {
+        return None;
+    }
+
+    let variant_names = variants.iter().map(|variant| format!("{variant}(..)"));
+    let (changes_channel_name, change_enum_name) = changes_idents(struct_name);
+    let stream = ClientStream::signal(
+        &syn::parse_quote!(#change_enum_name),
+        &changes_channel_name,
+        None,
+    );
+    let subscriber_declaration = &stream.declaration;
+
+    let declarations = quote! {
+        static #changes_channel_name: firmware_controller::__private::SignalChannel<#change_enum_name> =
+            firmware_controller::__private::SignalChannel::new();
+
+        // Not every controller has a consumer for the combined stream.
+        #[allow(dead_code)]
+        #[derive(Clone)]
+        pub enum #change_enum_name {
+            #(#variants(#types)),*
+        }
+
+        // Published fields only have to be `Clone`, so only the variant is shown.
+        impl core::fmt::Debug for #change_enum_name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                match self {
+                    #(Self::#variants(_) => f.write_str(#variant_names),)*
+                }
+            }
+        }
+
+        #subscriber_declaration
+    };
+
+    Some((declarations, stream))
+}
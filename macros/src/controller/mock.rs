@@ -0,0 +1,256 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{FnArg, Ident, ItemImpl, ReturnType};
+
+use crate::controller::client_trait::{self, TraitMethod};
+use crate::controller::subscription::ClientStream;
+use crate::util::{pascal_to_snake_case, snake_to_pascal_case};
+
+/// The channels of the mock, from its methods.
+pub(crate) fn channels() -> TokenStream {
+    quote! { self.channels }
+}
+
+/// Generate a mock implementing the client trait, only available under `cfg(#mock_cfg)`.
+///
+/// The calls to the request/response methods (`mocked_methods`) are recorded and answered with the
+/// return values queued by the test. All the other methods, i.e. the streams and local reads, are
+/// served by `stream_methods` from the mock's own copy of the channels of the `streams`, so that
+/// the test can feed them with the `inject_methods`.
+pub(crate) fn generate(
+    client_impl: &ItemImpl,
+    mocked_methods: &[Ident],
+    streams: &[&ClientStream],
+    stream_methods: &TokenStream,
+    inject_methods: &[TokenStream],
+    struct_name: &Ident,
+    mock_cfg: &TokenStream,
+) -> TokenStream {
+    let trait_name = client_trait::trait_ident(struct_name);
+    let mock_name = Ident::new(&format!("{struct_name}Mock"), struct_name.span());
+    let mock_type: syn::Type = syn::parse_quote! { #mock_name };
+    let channels_name = Ident::new(&format!("{struct_name}MockChannels"), struct_name.span());
+    let call_enum_name = Ident::new(&format!("{struct_name}Call"), struct_name.span());
+    let state_name = Ident::new(&format!("{struct_name}MockState"), struct_name.span());
+    let mod_name = Ident::new(
+        &format!("{}_mock", pascal_to_snake_case(&struct_name.to_string())),
+        struct_name.span(),
+    );
+
+    let methods = client_trait::trait_methods(client_impl);
+    let mut call_variants = Vec::new();
+    let mut return_queues = Vec::new();
+    let mut expect_methods = Vec::new();
+    let mut implementations = Vec::new();
+    for method in &methods {
+        let sig = &method.sig;
+        let method_name = &sig.ident;
+        if !mocked_methods.contains(method_name) {
+            let call = method.client_call(&mock_type, quote! { self });
+            implementations.push(quote! {
+                #sig {
+                    #call
+                }
+            });
+
+            continue;
+        }
+
+        let MockedMethod {
+            call_variant,
+            call_record,
+            return_queue,
+            expect_method,
+            return_value,
+        } = generate_mocked_method(method, &call_enum_name);
+        call_variants.push(call_variant);
+        return_queues.extend(return_queue);
+        expect_methods.extend(expect_method);
+        implementations.push(quote! {
+            #sig {
+                let mut state = std::sync::Mutex::lock(&self.state).unwrap();
+                state.calls.push(#call_record);
+                #return_value
+            }
+        });
+    }
+    let queue_names = return_queues
+        .iter()
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    let queue_name_strs = queue_names.iter().map(|name| name.to_string());
+    let queue_types = return_queues.iter().map(|(_, ty)| ty);
+    let (channel_declarations, channel_initializations): (Vec<_>, Vec<_>) =
+        streams.iter().map(|stream| stream.channel_fields()).unzip();
+
+    quote! {
+        #[cfg(#mock_cfg)]
+        pub use #mod_name::{#call_enum_name, #mock_name};
+
+        #[cfg(#mock_cfg)]
+        mod #mod_name {
+            extern crate std;
+
+            use super::*;
+
+            /// A call made through the mock.
+            #[derive(Debug)]
+            pub enum #call_enum_name {
+                #(#call_variants),*
+            }
+
+            #[derive(Default)]
+            struct #state_name {
+                calls: std::vec::Vec<#call_enum_name>,
+                #(#queue_names: std::collections::VecDeque<#queue_types>,)*
+            }
+
+            // Named after the statics they stand in for.
+            #[allow(non_snake_case)]
+            struct #channels_name {
+                #(#channel_declarations)*
+            }
+
+            /// A mock of the client, for testing the consumers of the controller.
+            ///
+            /// Each mock has its own channels, so it doesn't interfere with the controller or the
+            /// other mocks. Its clones share them, along with the recorded calls and the queued
+            /// return values.
+            #[derive(Clone)]
+            pub struct #mock_name {
+                state: std::sync::Arc<std::sync::Mutex<#state_name>>,
+                // The streams borrow their channels for `'static`, so they're leaked.
+                channels: &'static #channels_name,
+            }
+
+            impl #mock_name {
+                pub fn new() -> Self {
+                    Self {
+                        state: std::default::Default::default(),
+                        channels: std::boxed::Box::leak(std::boxed::Box::new(#channels_name {
+                            #(#channel_initializations)*
+                        })),
+                    }
+                }
+
+                #(#expect_methods)*
+
+                /// Take the calls made through the mock so far, in order.
+                pub fn take_calls(&self) -> std::vec::Vec<#call_enum_name> {
+                    core::mem::take(&mut std::sync::Mutex::lock(&self.state).unwrap().calls)
+                }
+
+                /// Panic if any of the expected return values was not consumed.
+                pub fn verify(&self) {
+                    let state = std::sync::Mutex::lock(&self.state).unwrap();
+                    #(
+                        if !state.#queue_names.is_empty() {
+                            panic!(
+                                "{} expected call(s) to `{}` were not made",
+                                state.#queue_names.len(),
+                                #queue_name_strs,
+                            );
+                        }
+                    )*
+                }
+
+                #(#inject_methods)*
+
+                #stream_methods
+            }
+
+            impl Default for #mock_name {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl #trait_name for #mock_name {
+                #(#implementations)*
+            }
+        }
+    }
+}
+
+/// Generated code for a method answered by the mock.
+struct MockedMethod {
+    /// The call enum variant declaration.
+    call_variant: TokenStream,
+    /// The call enum variant construction, from the method arguments.
+    call_record: TokenStream,
+    /// The queue of return values, named after the method, unless it returns `()`.
+    return_queue: Option<(Ident, syn::Type)>,
+    /// The method queuing a return value, unless the method returns `()`.
+    expect_method: Option<TokenStream>,
+    /// The return value of the method, with the mock state locked as `state`.
+    return_value: TokenStream,
+}
+
+fn generate_mocked_method(method: &TraitMethod, call_enum_name: &Ident) -> MockedMethod {
+    let method_name = &method.sig.ident;
+    let method_name_str = method_name.to_string();
+    let variant_name = Ident::new(&snake_to_pascal_case(&method_name_str), method_name.span());
+    let args = &method.args;
+    let arg_types = method.sig.inputs.iter().filter_map(|arg| match arg {
+        FnArg::Typed(arg) => Some(&arg.ty),
+        FnArg::Receiver(_) => None,
+    });
+    let (call_variant, call_record) = if args.is_empty() {
+        (
+            quote! { #variant_name },
+            quote! { #call_enum_name::#variant_name },
+        )
+    } else {
+        (
+            quote! { #variant_name { #(#args: #arg_types),* } },
+            quote! { #call_enum_name::#variant_name { #(#args),* } },
+        )
+    };
+
+    let return_type = match &method.sig.output {
+        ReturnType::Type(_, ty) if !matches!(&**ty, syn::Type::Tuple(t) if t.elems.is_empty()) => {
+            Some((**ty).clone())
+        }
+        _ => None,
+    };
+    let Some(return_type) = return_type else {
+        return MockedMethod {
+            call_variant,
+            call_record,
+            return_queue: None,
+            expect_method: None,
+            return_value: quote! {},
+        };
+    };
+
+    let queue_name = Ident::new(&format!("{method_name_str}_returns"), method_name.span());
+    let expect_method_name = Ident::new(&format!("expect_{method_name_str}"), method_name.span());
+    let expect_method = quote! {
+        /// Queue the return value of a call to the method.
+        pub fn #expect_method_name(&self, value: #return_type) -> &Self {
+            std::sync::Mutex::lock(&self.state)
+                .unwrap()
+                .#queue_name
+                .push_back(value);
+
+            self
+        }
+    };
+    let return_value = quote! {
+        match state.#queue_name.pop_front() {
+            Some(value) => value,
+            None => panic!(
+                "unexpected call to `{}`: no return value queued",
+                #method_name_str,
+            ),
+        }
+    };
+
+    MockedMethod {
+        call_variant,
+        call_record,
+        return_queue: Some((queue_name, return_type)),
+        expect_method: Some(expect_method),
+        return_value,
+    }
+}
@@ -0,0 +1,192 @@
+pub(crate) mod blocking_client;
+pub(crate) mod client_trait;
+pub(crate) mod item_impl;
+pub(crate) mod item_struct;
+pub(crate) mod mock;
+pub(crate) mod subscription;
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{punctuated::Punctuated, spanned::Spanned, Item, ItemMod, Meta, Result, Token};
+
+const ALL_CHANNEL_CAPACITY: usize = 8;
+
+/// Arguments of the `controller` attribute on the module.
+#[derive(Debug)]
+pub(crate) struct ControllerArgs {
+    /// Generate a mock of the client for tests (`#[controller(mock)]`), along with the
+    /// configuration predicate it's only available under (`test` unless given with
+    /// `#[controller(mock(cfg(...)))]`).
+    pub mock: Option<TokenStream>,
+    /// Generate a blocking client for std threads (`#[controller(std)]`).
+    pub std: bool,
+    /// Path of the `firmware_controller` crate (`#[controller(crate = "...")]`).
+    pub crate_path: syn::Path,
+    /// Generate a stream of the snapshots of the fields (`#[controller(snapshot(stream))]`).
+    pub snapshot_stream: bool,
+}
+
+impl Default for ControllerArgs {
+    fn default() -> Self {
+        Self {
+            mock: None,
+            std: false,
+            crate_path: syn::parse_quote! { ::firmware_controller },
+            snapshot_stream: false,
+        }
+    }
+}
+
+impl ControllerArgs {
+    fn parse(args: Punctuated<Meta, Token![,]>) -> Result<Self> {
+        let mut controller_args = ControllerArgs::default();
+        for arg in args {
+            match &arg {
+                Meta::Path(path) if path.is_ident("mock") => {
+                    controller_args.mock = Some(quote! { test })
+                }
+                Meta::List(list) if list.path.is_ident("mock") => {
+                    let mut mock_cfg = None;
+                    list.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("cfg") {
+                            let content;
+                            syn::parenthesized!(content in meta.input);
+                            mock_cfg = Some(content.parse()?);
+                            Ok(())
+                        } else {
+                            Err(meta.error("unknown mock option, expected `cfg`"))
+                        }
+                    })?;
+                    controller_args.mock = Some(mock_cfg.unwrap_or_else(|| quote! { test }));
+                }
+                Meta::Path(path) if path.is_ident("std") => controller_args.std = true,
+                Meta::List(list) if list.path.is_ident("snapshot") => {
+                    list.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("stream") {
+                            controller_args.snapshot_stream = true;
+                            Ok(())
+                        } else {
+                            Err(meta.error("unknown snapshot option, expected `stream`"))
+                        }
+                    })?;
+                }
+                Meta::NameValue(name_value) if name_value.path.is_ident("crate") => {
+                    let syn::Expr::Lit(syn::ExprLit {
+                        lit: syn::Lit::Str(path),
+                        ..
+                    }) = &name_value.value
+                    else {
+                        return Err(syn::Error::new(
+                            name_value.value.span(),
+                            "expected a string literal, e.g. `crate = \"firmware_controller\"`",
+                        ));
+                    };
+                    controller_args.crate_path = path.parse()?;
+                }
+                _ => return Err(syn::Error::new(
+                    arg.span(),
+                    "unknown controller argument, expected `mock`, `std`, `crate` or `snapshot(stream)`",
+                )),
+            }
+        }
+
+        Ok(controller_args)
+    }
+}
+
+pub(crate) fn expand_module(
+    input: ItemMod,
+    args: Punctuated<Meta, Token![,]>,
+) -> Result<TokenStream> {
+    let args = ControllerArgs::parse(args)?;
+    let vis = &input.vis;
+    let mod_name = &input.ident;
+    let span = input.span();
+
+    let (_, items) = input
+        .content
+        .ok_or_else(|| syn::Error::new(span, "Module must have a body"))?;
+
+    let mut struct_item = None;
+    let mut impl_item = None;
+    let mut other_items = Vec::new();
+
+    for item in items {
+        match item {
+            Item::Struct(s) => {
+                if struct_item.is_some() {
+                    return Err(syn::Error::new(
+                        s.span(),
+                        "Module must contain exactly one struct definition",
+                    ));
+                }
+                struct_item = Some(s);
+            }
+            Item::Impl(i) => {
+                if impl_item.is_some() {
+                    return Err(syn::Error::new(
+                        i.span(),
+                        "Module must contain exactly one impl block",
+                    ));
+                }
+                impl_item = Some(i);
+            }
+            other => other_items.push(other),
+        }
+    }
+
+    let struct_item = struct_item.ok_or_else(|| {
+        syn::Error::new(
+            span,
+            "Module must contain a struct definition for the controller",
+        )
+    })?;
+
+    let impl_item = impl_item.ok_or_else(|| {
+        syn::Error::new(span, "Module must contain an impl block for the controller")
+    })?;
+
+    let struct_name = &struct_item.ident;
+    if let syn::Type::Path(type_path) = &*impl_item.self_ty {
+        if let Some(ident) = type_path.path.get_ident() {
+            if ident != struct_name {
+                return Err(syn::Error::new(
+                    impl_item.span(),
+                    format!(
+                        "Impl block is for type '{}' but controller struct is named '{}'",
+                        ident, struct_name
+                    ),
+                ));
+            }
+        }
+    }
+
+    let batching = item_impl::has_atomic_methods(&impl_item);
+    let expanded_struct = item_struct::expand(struct_item, batching, args.snapshot_stream)?;
+    let expanded_impl = item_impl::expand(impl_item, &expanded_struct, &args)?;
+    let struct_tokens = expanded_struct.tokens;
+    let crate_path = &args.crate_path;
+    // Only the rate-limited fields need `embassy-time`, which is an optional dependency.
+    let time_import = expanded_struct
+        .published_fields
+        .iter()
+        .any(|field| field.pending_broadcast.is_some())
+        .then(|| quote! { use #crate_path::__private::embassy_time; });
+
+    Ok(quote! {
+        #vis mod #mod_name {
+            // The generated code refers to its dependencies by their bare names.
+            #[allow(unused_imports)]
+            use #crate_path::__private::{embassy_sync, futures};
+            #time_import
+            #[allow(unused_imports)]
+            use #crate_path as firmware_controller;
+
+            #(#other_items)*
+
+            #struct_tokens
+
+            #expanded_impl
+        }
+    })
+}
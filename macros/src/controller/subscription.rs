@@ -0,0 +1,205 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Ident, Type};
+
+/// A stream of the client, backed by a static channel.
+#[derive(Debug, Clone)]
+pub(crate) struct ClientStream {
+    /// Type of the stream (e.g., `firmware_controller::Published<State>`).
+    ty: TokenStream,
+    /// Type of the channel behind the stream.
+    channel_ty: TokenStream,
+    /// Declaration of the static keeping track of the subscribers of the stream.
+    pub declaration: TokenStream,
+    /// Name of the channel static.
+    channel_name: Ident,
+    /// Kind of the stream.
+    kind: StreamKind,
+    /// Name of the static keeping track of the subscribers of the stream.
+    subscribers_name: Ident,
+}
+
+#[derive(Debug, Clone)]
+enum StreamKind {
+    Published,
+    /// For sticky signals, `last` is the name of the static holding the last emitted value, and its
+    /// type.
+    Signal {
+        last: Option<(Ident, TokenStream)>,
+    },
+}
+
+impl ClientStream {
+    /// A stream of the values of the given `Watch` channel.
+    pub fn published(item_ty: &Type, watch_channel_name: &Ident) -> Self {
+        Self::new(
+            quote! { firmware_controller::Published<#item_ty> },
+            quote! { firmware_controller::__private::PublishedWatch<#item_ty> },
+            watch_channel_name,
+            StreamKind::Published,
+        )
+    }
+
+    /// A stream of the values emitted on the given `PubSubChannel`.
+    ///
+    /// For sticky signals, `last_name` is the static holding the last emitted value.
+    pub fn signal(item_ty: &Type, channel_name: &Ident, last_name: Option<&Ident>) -> Self {
+        Self::new(
+            quote! { firmware_controller::SignalStream<#item_ty> },
+            quote! { firmware_controller::__private::SignalChannel<#item_ty> },
+            channel_name,
+            StreamKind::Signal {
+                last: last_name.map(|last_name| {
+                    (
+                        last_name.clone(),
+                        quote! { firmware_controller::__private::SignalLast<#item_ty> },
+                    )
+                }),
+            },
+        )
+    }
+
+    fn new(
+        ty: TokenStream,
+        channel_ty: TokenStream,
+        channel_name: &Ident,
+        kind: StreamKind,
+    ) -> Self {
+        let subscribers_name =
+            Ident::new(&format!("{channel_name}_SUBSCRIBERS"), channel_name.span());
+
+        Self {
+            ty,
+            channel_ty,
+            declaration: quote! {
+                static #subscribers_name: firmware_controller::__private::Subscribers =
+                    firmware_controller::__private::Subscribers::new();
+            },
+            channel_name: channel_name.clone(),
+            kind,
+            subscribers_name,
+        }
+    }
+
+    /// Generate the client methods for subscribing to the stream:
+    ///
+    /// * `receive_<stream>`, failing if all the subscriber slots are taken.
+    /// * `subscribe_<stream>`, waiting for a subscriber slot to be freed if they're all taken.
+    /// * `<stream>_subscriber_count`, returning the number of current subscribers.
+    ///
+    /// The channels are the statics, unless `channels` is given (see [`channel_ref`]).
+    pub fn getters(&self, stream_name: &Ident, channels: Option<&TokenStream>) -> TokenStream {
+        let ty = &self.ty;
+        let constructor_args = self.constructor_args(channels);
+        let subscribers = channel_ref(&self.subscribers_name, channels);
+        let subscribe = self.subscribe(channels);
+        let receive_method_name = Ident::new(&format!("receive_{stream_name}"), stream_name.span());
+        let subscribe_method_name =
+            Ident::new(&format!("subscribe_{stream_name}"), stream_name.span());
+        let count_method_name = Ident::new(
+            &format!("{stream_name}_subscriber_count"),
+            stream_name.span(),
+        );
+
+        quote! {
+            pub fn #receive_method_name(
+                &self,
+            ) -> core::result::Result<#ty, firmware_controller::SubscribeError> {
+                <#ty>::new(#constructor_args)
+            }
+
+            pub async fn #subscribe_method_name(&self) -> #ty {
+                #subscribe
+            }
+
+            pub fn #count_method_name(&self) -> usize {
+                firmware_controller::__private::Subscribers::count(#subscribers)
+            }
+        }
+    }
+
+    /// For a stream of a `Watch` channel, the clearing of the last value it holds, which new
+    /// subscribers would otherwise get first. Signal channels don't hold anything for new
+    /// subscribers (besides the last value of sticky signals, reset along with the signal).
+    pub fn clear(&self) -> Option<TokenStream> {
+        let channel_name = &self.channel_name;
+
+        match self.kind {
+            StreamKind::Published => Some(quote! {
+                embassy_sync::watch::Watch::sender(&#channel_name).clear();
+            }),
+            StreamKind::Signal { .. } => None,
+        }
+    }
+
+    /// Subscribe to the stream, waiting for a subscriber slot to be freed if they're all taken.
+    pub fn subscribe(&self, channels: Option<&TokenStream>) -> TokenStream {
+        let ty = &self.ty;
+        let constructor_args = self.constructor_args(channels);
+
+        quote! { <#ty>::subscribe(#constructor_args).await }
+    }
+
+    /// The fields holding the channels of the stream in a struct of channels, and their
+    /// initialization.
+    pub fn channel_fields(&self) -> (TokenStream, TokenStream) {
+        let Self {
+            channel_ty,
+            channel_name,
+            subscribers_name,
+            ..
+        } = self;
+        let (last_declaration, last_initialization) = match &self.kind {
+            StreamKind::Signal {
+                last: Some((last_name, last_ty)),
+            } => (
+                quote! { #last_name: #last_ty, },
+                quote! {
+                    #last_name: embassy_sync::blocking_mutex::Mutex::new(
+                        core::cell::RefCell::new(None),
+                    ),
+                },
+            ),
+            _ => (quote! {}, quote! {}),
+        };
+
+        (
+            quote! {
+                #channel_name: #channel_ty,
+                #subscribers_name: firmware_controller::__private::Subscribers,
+                #last_declaration
+            },
+            quote! {
+                #channel_name: <#channel_ty>::new(),
+                #subscribers_name: firmware_controller::__private::Subscribers::new(),
+                #last_initialization
+            },
+        )
+    }
+
+    fn constructor_args(&self, channels: Option<&TokenStream>) -> TokenStream {
+        let channel = channel_ref(&self.channel_name, channels);
+        let subscribers = channel_ref(&self.subscribers_name, channels);
+        let last = match &self.kind {
+            StreamKind::Published => quote! {},
+            StreamKind::Signal {
+                last: Some((last_name, _)),
+            } => {
+                let last = channel_ref(last_name, channels);
+                quote! { Some(#last), }
+            }
+            StreamKind::Signal { last: None } => quote! { None, },
+        };
+
+        quote! { #channel, #last #subscribers }
+    }
+}
+
+/// A reference to the channel static of the given name or, if `channels` is given, to the field of
+/// the same name of that struct of channels (e.g., those of a mock).
+pub(crate) fn channel_ref(name: &Ident, channels: Option<&TokenStream>) -> TokenStream {
+    match channels {
+        Some(channels) => quote! { &#channels.#name },
+        None => quote! { &#name },
+    }
+}
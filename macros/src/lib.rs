@@ -0,0 +1,19 @@
+//! Procedural macros of the `firmware-controller` crate, which re-exports them. See its
+//! documentation for more information.
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, punctuated::Punctuated, ItemMod, Meta, Token};
+
+mod controller;
+mod util;
+
+/// See the `firmware-controller` crate documentation for more information.
+#[proc_macro_attribute]
+pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr with Punctuated<Meta, Token![,]>::parse_terminated);
+
+    let input = parse_macro_input!(item as ItemMod);
+    controller::expand_module(input, args)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
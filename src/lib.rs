@@ -1,18 +1,45 @@
 #![doc = include_str!("../README.md")]
+#![no_std]
 
-use proc_macro::TokenStream;
-use syn::{parse_macro_input, punctuated::Punctuated, ItemMod, Meta, Token};
+#[cfg(feature = "std")]
+extern crate std;
 
-mod controller;
-mod util;
+use core::future::Future;
 
-/// See the crate-level documentation for more information.
-#[proc_macro_attribute]
-pub fn controller(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let _args = parse_macro_input!(attr with Punctuated<Meta, Token![,]>::parse_terminated);
+pub use firmware_controller_macros::controller;
 
-    let input = parse_macro_input!(item as ItemMod);
-    controller::expand_module(input)
-        .unwrap_or_else(|e| e.to_compile_error())
-        .into()
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use stream::{Published, SignalStream, SubscribeError};
+
+/// Dependencies of the generated code, so that users don't need to depend on them (in matching
+/// versions) themselves. Not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use embassy_sync;
+    #[cfg(feature = "time")]
+    pub use embassy_time;
+    pub use futures;
+
+    pub use crate::stream::{
+        PublishedSender, PublishedWatch, SignalChannel, SignalLast, SignalPublisher, Subscribers,
+    };
+}
+
+/// A controller generated by the [`controller`] macro.
+pub trait Controller {
+    /// The client of the controller.
+    type Client: Copy + Default;
+
+    /// Run the controller logic. It never returns.
+    fn run(self) -> impl Future<Output = ()>;
+
+    /// Reset the state kept in the statics of the controller: the requests and replies left in
+    /// its channels, the last values of its sticky signals and the last broadcast values of its
+    /// published fields and snapshots.
+    ///
+    /// It must not be called while the controller is running.
+    fn reset();
 }
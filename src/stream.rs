@@ -0,0 +1,261 @@
+//! The streams of the clients: [`Published`] for the published fields and the snapshots, and
+//! [`SignalStream`] for everything else (signals, transitions, alarms, changes and events).
+
+use core::cell::{Cell, RefCell};
+use core::pin::Pin;
+use core::task::{Context, Poll};
+
+use embassy_sync::blocking_mutex::raw::CriticalSectionRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
+use embassy_sync::pubsub::{PubSubChannel, Publisher, Subscriber};
+use embassy_sync::waitqueue::MultiWakerRegistration;
+use embassy_sync::watch::{self, Watch};
+use futures::Stream;
+
+/// The maximum number of subscribers of each stream.
+pub const MAX_SUBSCRIBERS: usize = 16;
+/// The number of values a signal stream can lag behind before missing some.
+const CHANNEL_CAPACITY: usize = 8;
+const MAX_PUBLISHERS: usize = 1;
+
+/// The channel behind a [`Published`] stream.
+#[doc(hidden)]
+pub type PublishedWatch<T> = Watch<CriticalSectionRawMutex, T, MAX_SUBSCRIBERS>;
+
+/// The sender of a [`PublishedWatch`].
+#[doc(hidden)]
+pub type PublishedSender<T> = watch::Sender<'static, CriticalSectionRawMutex, T, MAX_SUBSCRIBERS>;
+
+/// The channel behind a [`SignalStream`].
+#[doc(hidden)]
+pub type SignalChannel<T> =
+    PubSubChannel<CriticalSectionRawMutex, T, CHANNEL_CAPACITY, MAX_SUBSCRIBERS, MAX_PUBLISHERS>;
+
+/// The publisher of a [`SignalChannel`].
+#[doc(hidden)]
+pub type SignalPublisher<T> = Publisher<
+    'static,
+    CriticalSectionRawMutex,
+    T,
+    CHANNEL_CAPACITY,
+    MAX_SUBSCRIBERS,
+    MAX_PUBLISHERS,
+>;
+
+/// The last value emitted on a sticky [`SignalChannel`].
+#[doc(hidden)]
+pub type SignalLast<T> = Mutex<CriticalSectionRawMutex, RefCell<Option<T>>>;
+
+/// Error returned when subscribing to a stream fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscribeError {
+    /// All the subscriber slots of the stream are taken. Dropping a subscriber frees its slot.
+    MaxSubscribersReached,
+}
+
+/// Keeps count of the subscribers of a stream, and of the tasks waiting for a subscriber slot.
+#[doc(hidden)]
+pub struct Subscribers {
+    count: Mutex<CriticalSectionRawMutex, Cell<usize>>,
+    waiters: Mutex<CriticalSectionRawMutex, RefCell<MultiWakerRegistration<MAX_SUBSCRIBERS>>>,
+}
+
+impl Subscribers {
+    pub const fn new() -> Self {
+        Self {
+            count: Mutex::new(Cell::new(0)),
+            waiters: Mutex::new(RefCell::new(MultiWakerRegistration::new())),
+        }
+    }
+
+    /// The number of subscribers currently subscribed.
+    pub fn count(&self) -> usize {
+        self.count.lock(Cell::get)
+    }
+
+    fn slot(&'static self) -> Slot {
+        self.count.lock(|count| count.set(count.get() + 1));
+
+        Slot(self)
+    }
+
+    /// Subscribe, waiting for a subscriber slot to be freed if they're all taken.
+    async fn wait_for_slot<S>(
+        &self,
+        mut subscribe: impl FnMut() -> Result<S, SubscribeError>,
+    ) -> S {
+        core::future::poll_fn(|cx| {
+            // Register first, so that we can't miss a slot freed in between.
+            self.waiters
+                .lock(|waiters| waiters.borrow_mut().register(cx.waker()));
+            match subscribe() {
+                Ok(subscriber) => Poll::Ready(subscriber),
+                Err(_) => Poll::Pending,
+            }
+        })
+        .await
+    }
+}
+
+impl Default for Subscribers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The subscriber slot taken by a stream, waking the tasks waiting for a slot when dropped.
+///
+/// It must be the last field of the stream, so that the slot is already freed when the waiting
+/// tasks are woken.
+struct Slot(&'static Subscribers);
+
+impl Drop for Slot {
+    fn drop(&mut self) {
+        self.0.count.lock(|count| count.set(count.get() - 1));
+        self.0.waiters.lock(|waiters| waiters.borrow_mut().wake());
+    }
+}
+
+/// Stream of the values of a published field (or of the snapshots of a controller).
+///
+/// The first value yielded is the current one at subscription time, if any, and the next ones are
+/// yielded as they're broadcast. Only the latest value is kept, so intermediate values may be
+/// missed if the stream isn't polled in between.
+pub struct Published<T: Clone + 'static> {
+    receiver: watch::Receiver<'static, CriticalSectionRawMutex, T, MAX_SUBSCRIBERS>,
+    first_poll: bool,
+    _slot: Slot,
+}
+
+impl<T: Clone + 'static> Published<T> {
+    #[doc(hidden)]
+    pub fn new(
+        watch: &'static PublishedWatch<T>,
+        subscribers: &'static Subscribers,
+    ) -> Result<Self, SubscribeError> {
+        watch
+            .receiver()
+            .map(|receiver| Self {
+                receiver,
+                first_poll: true,
+                _slot: subscribers.slot(),
+            })
+            .ok_or(SubscribeError::MaxSubscribersReached)
+    }
+
+    #[doc(hidden)]
+    pub async fn subscribe(
+        watch: &'static PublishedWatch<T>,
+        subscribers: &'static Subscribers,
+    ) -> Self {
+        subscribers
+            .wait_for_slot(|| Self::new(watch, subscribers))
+            .await
+    }
+
+    /// Wait for a value matching the predicate, be it the current one or a later one, and return
+    /// it.
+    pub async fn wait_for(&mut self, predicate: impl Fn(&T) -> bool) -> T {
+        self.receiver.get_and(predicate).await
+    }
+}
+
+// Nothing is structurally pinned.
+impl<T: Clone + 'static> Unpin for Published<T> {}
+
+impl<T: Clone + 'static> Stream for Published<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        use core::future::Future;
+
+        let this = self.get_mut();
+
+        // First poll: return current value immediately if available.
+        if this.first_poll {
+            this.first_poll = false;
+            if let Some(value) = this.receiver.try_get() {
+                return Poll::Ready(Some(value));
+            }
+        }
+
+        // Create changed() future and poll it in place.
+        let fut = this.receiver.changed();
+        futures::pin_mut!(fut);
+        fut.poll(cx).map(Some)
+    }
+}
+
+/// Stream of the values emitted on a signal channel: the arguments of a signal, the transitions or
+/// the alarm of a published field, or the changes or events of a controller.
+///
+/// Unlike [`Published`], every value is yielded, so the stream must be continuously polled.
+/// Otherwise, the values are missed once it lags too far behind.
+pub struct SignalStream<T: Clone + 'static> {
+    subscriber: Subscriber<
+        'static,
+        CriticalSectionRawMutex,
+        T,
+        CHANNEL_CAPACITY,
+        MAX_SUBSCRIBERS,
+        MAX_PUBLISHERS,
+    >,
+    // For sticky signals, the last value emitted before subscription, yielded first.
+    last: Option<T>,
+    _slot: Slot,
+}
+
+impl<T: Clone + 'static> SignalStream<T> {
+    #[doc(hidden)]
+    pub fn new(
+        channel: &'static SignalChannel<T>,
+        last: Option<&'static SignalLast<T>>,
+        subscribers: &'static Subscribers,
+    ) -> Result<Self, SubscribeError> {
+        let subscribe = |last: Option<T>| {
+            channel
+                .subscriber()
+                .map(|subscriber| Self {
+                    subscriber,
+                    last,
+                    _slot: subscribers.slot(),
+                })
+                .map_err(|_| SubscribeError::MaxSubscribersReached)
+        };
+
+        match last {
+            // Subscribe while holding the lock so that we can't miss a value emitted in between.
+            Some(last) => last.lock(|last| subscribe(last.borrow().clone())),
+            None => subscribe(None),
+        }
+    }
+
+    #[doc(hidden)]
+    pub async fn subscribe(
+        channel: &'static SignalChannel<T>,
+        last: Option<&'static SignalLast<T>>,
+        subscribers: &'static Subscribers,
+    ) -> Self {
+        subscribers
+            .wait_for_slot(|| Self::new(channel, last, subscribers))
+            .await
+    }
+}
+
+// Nothing is structurally pinned.
+impl<T: Clone + 'static> Unpin for SignalStream<T> {}
+
+impl<T: Clone + 'static> Stream for SignalStream<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let this = self.get_mut();
+
+        // First poll: return the last emitted value if there is one.
+        if let Some(last) = this.last.take() {
+            return Poll::Ready(Some(last));
+        }
+
+        Pin::new(&mut *this.subscriber).poll_next(cx)
+    }
+}
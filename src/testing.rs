@@ -0,0 +1,200 @@
+//! Helpers for testing controllers on the host.
+//!
+//! The channels of a controller are statics, so the tests of the same controller can't run
+//! concurrently, and a test could see the leftovers of the previous one. The helpers take care of
+//! running such tests one at a time and of resetting the statics in between.
+
+use core::any::TypeId;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::pin::pin;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Poll;
+use core::time::Duration;
+use std::panic;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::vec::Vec;
+
+use futures::future::{self, Either};
+use futures::task::AtomicWaker;
+
+use crate::Controller;
+
+/// The timeout of [`with_client`].
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Build the controller with `new` and run it on its own thread, until the returned handle is
+/// dropped.
+///
+/// Only one controller of a given type runs at a time: if one is already running (e.g., in a test
+/// running concurrently), this waits for its handle to be dropped. As building the controller
+/// broadcasts the initial values of its fields, it's only built once the other one is stopped.
+pub fn spawn_controller<C, N>(new: N) -> ControllerHandle<C>
+where
+    C: Controller + Send + 'static,
+    N: FnOnce() -> C,
+{
+    let lock = TypeLock::acquire(TypeId::of::<C>());
+    // Drop the leftovers of a controller not run through the helpers.
+    C::reset();
+    let controller = new();
+
+    let stop = Arc::new(Notification::default());
+    let thread = thread::spawn({
+        let stop = stop.clone();
+        move || {
+            futures::executor::block_on(async {
+                future::select(pin!(controller.run()), pin!(stop.wait())).await;
+            })
+        }
+    });
+
+    ControllerHandle {
+        stop,
+        thread: Some(thread),
+        _lock: lock,
+        _controller: PhantomData,
+    }
+}
+
+/// Build the controller with `new` and run it and the test, which is given a client, until the
+/// test completes (see [`spawn_controller`]).
+///
+/// Panics if the test doesn't complete within [`DEFAULT_TIMEOUT`].
+pub fn with_client<C, N, F, Fut>(new: N, test: F) -> Fut::Output
+where
+    C: Controller + Send + 'static,
+    N: FnOnce() -> C,
+    F: FnOnce(C::Client) -> Fut,
+    Fut: Future,
+{
+    with_client_timeout(new, DEFAULT_TIMEOUT, test)
+}
+
+/// Like [`with_client`], with a custom timeout.
+pub fn with_client_timeout<C, N, F, Fut>(new: N, timeout: Duration, test: F) -> Fut::Output
+where
+    C: Controller + Send + 'static,
+    N: FnOnce() -> C,
+    F: FnOnce(C::Client) -> Fut,
+    Fut: Future,
+{
+    let handle = spawn_controller(new);
+
+    block_on_timeout(timeout, test(handle.client()))
+}
+
+/// Block the current thread on the future, panicking if it doesn't complete within the timeout.
+pub fn block_on_timeout<F: Future>(timeout: Duration, future: F) -> F::Output {
+    futures::executor::block_on(async {
+        match future::select(pin!(future), pin!(sleep(timeout))).await {
+            Either::Left((output, _)) => output,
+            Either::Right(_) => panic!("timed out after {timeout:?}"),
+        }
+    })
+}
+
+/// Handle of a controller running on its own thread.
+///
+/// Dropping it stops the controller and resets its statics.
+pub struct ControllerHandle<C: Controller> {
+    stop: Arc<Notification>,
+    thread: Option<JoinHandle<()>>,
+    // Released once the controller is stopped and reset.
+    _lock: TypeLock,
+    _controller: PhantomData<C>,
+}
+
+impl<C: Controller> ControllerHandle<C> {
+    /// A client of the controller.
+    pub fn client(&self) -> C::Client {
+        C::Client::default()
+    }
+}
+
+impl<C: Controller> Drop for ControllerHandle<C> {
+    fn drop(&mut self) {
+        self.stop.notify();
+        let result = self.thread.take().map(JoinHandle::join);
+        C::reset();
+
+        // Don't hide a panic of the controller, unless we're already panicking.
+        if let Some(Err(payload)) = result {
+            if !thread::panicking() {
+                panic::resume_unwind(payload);
+            }
+        }
+    }
+}
+
+/// A one-off notification.
+#[derive(Default)]
+struct Notification {
+    notified: AtomicBool,
+    waker: AtomicWaker,
+}
+
+impl Notification {
+    fn notify(&self) {
+        self.notified.store(true, Ordering::Release);
+        self.waker.wake();
+    }
+
+    async fn wait(&self) {
+        future::poll_fn(|cx| {
+            // Register first, so that we can't miss a notification in between.
+            self.waker.register(cx.waker());
+            if self.notified.load(Ordering::Acquire) {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        })
+        .await
+    }
+}
+
+/// Wait for the duration, without blocking the current thread.
+async fn sleep(duration: Duration) {
+    let elapsed = Arc::new(Notification::default());
+    // Dropped along with the future, which ends the timer thread early.
+    let (_cancel, cancelled) = mpsc::channel::<()>();
+    thread::spawn({
+        let elapsed = elapsed.clone();
+        move || {
+            if let Err(RecvTimeoutError::Timeout) = cancelled.recv_timeout(duration) {
+                elapsed.notify();
+            }
+        }
+    });
+
+    elapsed.wait().await
+}
+
+/// The types of the controllers currently running through the helpers.
+static RUNNING: Mutex<Vec<TypeId>> = Mutex::new(Vec::new());
+static STOPPED: Condvar = Condvar::new();
+
+/// Lock ensuring only one controller of a given type runs at a time.
+struct TypeLock(TypeId);
+
+impl TypeLock {
+    fn acquire(type_id: TypeId) -> Self {
+        let mut running = RUNNING.lock().unwrap();
+        while running.contains(&type_id) {
+            running = STOPPED.wait(running).unwrap();
+        }
+        running.push(type_id);
+
+        Self(type_id)
+    }
+}
+
+impl Drop for TypeLock {
+    fn drop(&mut self) {
+        RUNNING.lock().unwrap().retain(|type_id| *type_id != self.0);
+        STOPPED.notify_all();
+    }
+}
@@ -60,8 +60,7 @@ mod test_controller {
 
         pub async fn trigger_error(&mut self) -> Result<(), TestError> {
             self.set_state(State::Error).await;
-            self.error_occurred(42, "Test error".try_into().unwrap())
-                .await;
+            self.error_occurred(42, "Test error".into()).await;
             Err(TestError::OperationFailed)
         }
 
@@ -220,6 +219,127 @@ async fn controller_task(controller: Controller) {
     controller.run().await;
 }
 
+/// Test that the events stream yields all signals, in order.
+#[controller]
+mod events_test_controller {
+    pub struct Controller {
+        faults: u32,
+    }
+
+    impl Controller {
+        #[controller(signal)]
+        pub async fn fault(&self, code: u32);
+
+        #[controller(signal)]
+        pub async fn recovered(&self);
+
+        pub async fn fail_and_recover(&mut self, code: u32) {
+            self.faults += 1;
+            self.fault(code).await;
+            self.recovered().await;
+        }
+    }
+}
+
+#[test]
+fn test_events_stream() {
+    use events_test_controller::{Controller, ControllerEvent};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(0),
+        |mut client| async move {
+            let mut event_stream = client.receive_events().expect("Failed to subscribe");
+
+            client.fail_and_recover(7).await;
+
+            let event = event_stream.next().await.expect("Should receive event");
+            match event {
+                ControllerEvent::Fault(args) => assert_eq!(args.code, 7),
+                _ => panic!("First event should be Fault"),
+            }
+            let event = event_stream.next().await.expect("Should receive event");
+            assert!(
+                matches!(event, ControllerEvent::Recovered(_)),
+                "Second event should be Recovered"
+            );
+        },
+    );
+}
+
+/// Test that the changes stream yields the changes of all published fields, in order.
+#[controller]
+mod changes_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        enabled: bool,
+        #[controller(publish, setter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_changes_stream() {
+    use changes_test_controller::{Controller, ControllerChange};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(false, 0),
+        |client| async move {
+            let mut change_stream = client.receive_changes().expect("Failed to subscribe");
+
+            client.set_enabled(true).await;
+            client.set_level(3).await;
+            client.set_enabled(false).await;
+
+            let change = change_stream.next().await.expect("Should receive change");
+            assert!(matches!(change, ControllerChange::Enabled(true)));
+            let change = change_stream.next().await.expect("Should receive change");
+            assert!(matches!(change, ControllerChange::Level(3)));
+            let change = change_stream.next().await.expect("Should receive change");
+            assert!(matches!(change, ControllerChange::Enabled(false)));
+        },
+    );
+}
+
+/// Test that the transitions stream yields both the previous and the new values.
+#[controller]
+mod transitions_test_controller {
+    use super::State;
+
+    pub struct Controller {
+        #[controller(publish(transitions), setter)]
+        state: State,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_transitions_stream() {
+    use transitions_test_controller::Controller;
+
+    firmware_controller::testing::with_client(
+        || Controller::new(State::Idle),
+        |client| async move {
+            let mut transition_stream = client
+                .receive_state_transitions()
+                .expect("Failed to subscribe");
+
+            client.set_state(State::Active).await;
+            client.set_state(State::Error).await;
+
+            for expected in [(State::Idle, State::Active), (State::Active, State::Error)] {
+                let transition = transition_stream
+                    .next()
+                    .await
+                    .expect("Should receive state transition");
+                assert_eq!(transition, expected);
+            }
+        },
+    );
+}
+
 /// Test that visibility specifiers on struct fields are preserved.
 #[controller]
 mod visibility_test_controller {
@@ -265,7 +385,7 @@ fn test_visibility_on_fields() {
         // Use generated getters from #[controller(getter)] attribute.
         assert_eq!(client.public_field().await, 42);
         assert_eq!(client.crate_field().await, -1);
-        assert_eq!(client.private_field().await, true);
+        assert!(client.private_field().await);
     });
 }
 
@@ -273,3 +393,1240 @@ fn test_visibility_on_fields() {
 async fn visibility_controller_task(controller: visibility_test_controller::Controller) {
     controller.run().await;
 }
+
+/// Test that sticky signals replay the last emitted value to late subscribers.
+#[controller]
+mod sticky_test_controller {
+    pub struct Controller {
+        faults: u32,
+    }
+
+    impl Controller {
+        #[controller(signal(sticky))]
+        pub async fn fault(&self, code: u32);
+
+        pub async fn raise_fault(&mut self, code: u32) {
+            self.faults += 1;
+            self.fault(code).await;
+        }
+    }
+}
+
+#[test]
+fn test_sticky_signal() {
+    firmware_controller::testing::with_client(
+        || sticky_test_controller::Controller::new(0),
+        |mut client| async move {
+            client.raise_fault(1).await;
+            client.raise_fault(2).await;
+
+            // A late subscriber gets the most recent value first.
+            let mut fault_stream = client.receive_fault().expect("Failed to subscribe");
+            let fault = fault_stream
+                .next()
+                .await
+                .expect("Should receive last fault");
+            assert_eq!(fault.code, 2, "Should replay the last fault");
+
+            // Followed by the new ones.
+            client.raise_fault(3).await;
+            let fault = fault_stream.next().await.expect("Should receive new fault");
+            assert_eq!(fault.code, 3, "Should receive the new fault");
+        },
+    );
+}
+
+/// Test that a sticky signal value is never delivered twice to a subscriber that comes while the
+/// signal is being emitted.
+#[controller]
+mod sticky_race_test_controller {
+    pub struct Controller {
+        #[controller(getter)]
+        faults: u32,
+    }
+
+    impl Controller {
+        #[controller(signal(sticky))]
+        pub async fn fault(&self, code: u32);
+    }
+}
+
+#[test]
+fn test_sticky_signal_no_duplicate() {
+    use futures::FutureExt;
+
+    let controller = sticky_race_test_controller::Controller::new(0);
+    let client = sticky_race_test_controller::ControllerClient::new();
+
+    futures::executor::block_on(async {
+        // A lagging events subscriber makes the emission wait once the events channel is full.
+        let mut events = client.receive_events().expect("Failed to subscribe");
+        for code in 1..=8 {
+            controller.fault(code).await;
+        }
+        let mut emission = Box::pin(controller.fault(9));
+        assert!((&mut emission).now_or_never().is_none());
+
+        // Subscribing in the middle of the emission.
+        let mut faults = client.receive_fault().expect("Failed to subscribe");
+        events.next().await;
+        emission.await;
+
+        assert_eq!(faults.next().await.map(|fault| fault.code), Some(8));
+        assert_eq!(faults.next().await.map(|fault| fault.code), Some(9));
+        assert!(faults.next().now_or_never().is_none());
+    });
+}
+
+/// Test that published fields only need to be `Clone`.
+#[derive(Clone)]
+pub struct Reading(pub u32);
+
+#[controller]
+mod clone_only_test_controller {
+    use super::Reading;
+
+    pub struct Controller {
+        #[controller(publish, setter)]
+        reading: Reading,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_clone_only_published_field() {
+    use clone_only_test_controller::{Controller, ControllerChange};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(Reading(1)),
+        |client| async move {
+            let mut changes = client.receive_changes().expect("Failed to subscribe");
+            client.set_reading(Reading(2)).await;
+
+            let change = changes.next().await.unwrap();
+            assert_eq!(format!("{change:?}"), "Reading(..)");
+            let ControllerChange::Reading(reading) = change;
+            assert_eq!(reading.0, 2);
+        },
+    );
+}
+
+/// Test that distinct published fields don't broadcast unchanged values.
+#[controller]
+mod distinct_test_controller {
+    pub struct Controller {
+        #[controller(publish(distinct), setter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_distinct_publish() {
+    firmware_controller::testing::with_client(
+        || distinct_test_controller::Controller::new(1),
+        |client| async move {
+            let mut change_stream = client.receive_changes().expect("Failed to subscribe");
+
+            for level in [1, 1, 2, 2, 3] {
+                client.set_level(level).await;
+            }
+
+            // Only actual changes are broadcast.
+            for expected in [2, 3] {
+                let distinct_test_controller::ControllerChange::Level(level) =
+                    change_stream.next().await.expect("Should receive change");
+                assert_eq!(level, expected, "Only changed values should be broadcast");
+            }
+        },
+    );
+}
+
+/// Test that rate-limited published fields coalesce broadcasts but always deliver the final value.
+#[controller]
+mod rate_limit_test_controller {
+    pub struct Controller {
+        #[controller(publish(debounce_ms = 50), setter)]
+        temperature: i32,
+        #[controller(publish(min_interval_ms = 100), setter)]
+        voltage: u32,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_rate_limited_publish() {
+    use rate_limit_test_controller::{Controller, ControllerChange};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(0, 0),
+        |client| async move {
+            let mut change_stream = client.receive_changes().expect("Failed to subscribe");
+
+            // Debounced: the value that settles is always broadcast. Intermediate ones are only
+            // broadcast if the updates are slowed down beyond the debounce delay, so only check
+            // their order.
+            for temperature in [1, 2, 3] {
+                client.set_temperature(temperature).await;
+            }
+            let mut last = 0;
+            while last != 3 {
+                let change = change_stream.next().await.expect("Should receive change");
+                let ControllerChange::Temperature(temperature) = change else {
+                    panic!("Expected a temperature change, got {change:?}");
+                };
+                assert!(
+                    temperature > last,
+                    "Temperatures should be broadcast in order"
+                );
+                last = temperature;
+            }
+
+            // Throttled: the first value goes through immediately and the final one at the end
+            // of the interval, with possibly some others in between if the updates are slowed
+            // down beyond the interval.
+            for voltage in [10, 11, 12] {
+                client.set_voltage(voltage).await;
+            }
+            let change = change_stream.next().await.expect("Should receive change");
+            assert!(
+                matches!(change, ControllerChange::Voltage(10)),
+                "First voltage should be broadcast immediately, got {change:?}"
+            );
+            let mut last = 10;
+            while last != 12 {
+                let change = change_stream.next().await.expect("Should receive change");
+                let ControllerChange::Voltage(voltage) = change else {
+                    panic!("Expected a voltage change, got {change:?}");
+                };
+                assert!(voltage > last, "Voltages should be broadcast in order");
+                last = voltage;
+            }
+        },
+    );
+}
+
+/// Test that threshold alarms are raised and cleared with hysteresis.
+#[controller]
+mod alarm_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter, alarm(above = 80, clear_below = 75))]
+        current: u32,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_alarm() {
+    use alarm_test_controller::{Controller, ControllerAlarm};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(50),
+        |client| async move {
+            let mut alarm_stream = client.receive_current_alarm().expect("Failed to subscribe");
+
+            for current in [81, 90, 77, 74, 85] {
+                client.set_current(current).await;
+            }
+
+            for expected in [
+                ControllerAlarm::Raised(81),
+                ControllerAlarm::Cleared(74),
+                ControllerAlarm::Raised(85),
+            ] {
+                let alarm = alarm_stream.next().await.expect("Should receive alarm");
+                assert_eq!(alarm, expected);
+            }
+
+            // A lagging alarm subscriber doesn't block the setter, it misses the oldest alarms.
+            for _ in 0..10 {
+                client.set_current(70).await;
+                client.set_current(90).await;
+            }
+            assert_eq!(client.current_now(), Some(90));
+            let alarm = alarm_stream.next().await.expect("Should receive alarm");
+            assert!(matches!(
+                alarm,
+                ControllerAlarm::Cleared(70) | ControllerAlarm::Raised(90)
+            ));
+        },
+    );
+}
+
+#[test]
+fn test_alarm_initial_value_above_threshold() {
+    use alarm_test_controller::{Controller, ControllerAlarm};
+
+    firmware_controller::testing::with_client(
+        || Controller::new(90),
+        |client| async move {
+            let mut alarm_stream = client.receive_current_alarm().expect("Failed to subscribe");
+
+            // The alarm isn't considered raised until the first update, which raises it.
+            client.set_current(85).await;
+            client.set_current(70).await;
+
+            for expected in [ControllerAlarm::Raised(85), ControllerAlarm::Cleared(70)] {
+                let alarm = alarm_stream.next().await.expect("Should receive alarm");
+                assert_eq!(alarm, expected);
+            }
+        },
+    );
+}
+
+/// Test that change hooks run after updates from both clients and internal setters.
+#[controller]
+mod on_change_test_controller {
+    pub struct Controller {
+        #[controller(setter, on_change = "apply_mode")]
+        mode: u8,
+        #[controller(publish, on_change = "apply_level")]
+        level: u8,
+        #[controller(getter)]
+        last_mode_change: (u8, u8),
+        #[controller(getter)]
+        last_level_change: (u8, u8),
+    }
+
+    impl Controller {
+        async fn apply_mode(&mut self, old: u8, new: u8) {
+            self.last_mode_change = (old, new);
+        }
+
+        async fn apply_level(&mut self, old: u8, new: u8) {
+            self.last_level_change = (old, new);
+        }
+
+        pub async fn bump_level(&mut self) {
+            self.set_level(self.level + 1).await;
+        }
+    }
+}
+
+#[test]
+fn test_on_change() {
+    firmware_controller::testing::with_client(
+        || on_change_test_controller::Controller::new(1, 10, (0, 0), (0, 0)),
+        |mut client| async move {
+            client.set_mode(2).await;
+            assert_eq!(client.last_mode_change().await, (1, 2));
+
+            client.bump_level().await;
+            assert_eq!(client.last_level_change().await, (10, 11));
+        },
+    );
+}
+
+/// Test that validated setters reject invalid values.
+#[controller]
+mod validate_test_controller {
+    #[derive(Debug, PartialEq)]
+    pub enum ModeError {
+        Invalid(u8),
+    }
+
+    pub struct Controller {
+        #[controller(setter, getter, validate = "check_mode")]
+        mode: u8,
+    }
+
+    impl Controller {
+        fn check_mode(&self, mode: &u8) -> Result<(), ModeError> {
+            if *mode <= 3 {
+                Ok(())
+            } else {
+                Err(ModeError::Invalid(*mode))
+            }
+        }
+    }
+}
+
+#[test]
+fn test_validated_setter() {
+    use validate_test_controller::ModeError;
+
+    firmware_controller::testing::with_client(
+        || validate_test_controller::Controller::new(0),
+        |client| async move {
+            assert_eq!(client.set_mode(2).await, Ok(()));
+            assert_eq!(client.mode().await, 2);
+
+            assert_eq!(client.set_mode(7).await, Err(ModeError::Invalid(7)));
+            assert_eq!(client.mode().await, 2);
+        },
+    );
+}
+
+/// Test that ranged setters reject or clamp out-of-range values.
+#[controller]
+mod range_test_controller {
+    pub struct Controller {
+        #[controller(setter, getter, range = 0..=100, unit = "mA")]
+        current: u32,
+        #[controller(publish, setter, getter, range = -10..=10, clamp)]
+        offset: i8,
+        #[controller(publish, setter, range = 0..=10)]
+        level: u8,
+    }
+
+    impl Controller {
+        // Sets the fields from the controller itself, rather than through the client setters.
+        pub async fn force(
+            &mut self,
+            offset: i8,
+            level: u8,
+        ) -> Result<(), ControllerOutOfRange<u8>> {
+            self.set_offset(offset).await;
+
+            self.set_level(level).await
+        }
+    }
+}
+
+#[test]
+fn test_range() {
+    use range_test_controller::{Controller, ControllerOutOfRange};
+
+    assert_eq!(Controller::CURRENT_RANGE, 0..=100);
+    assert_eq!(Controller::CURRENT_UNIT, "mA");
+    assert_eq!(Controller::OFFSET_RANGE, -10..=10);
+
+    firmware_controller::testing::with_client(
+        || Controller::new(0, 0, 0),
+        |mut client| async move {
+            assert_eq!(client.set_current(100).await, Ok(()));
+            assert_eq!(
+                client.set_current(101).await,
+                Err(ControllerOutOfRange(101))
+            );
+            assert_eq!(client.current().await, 100);
+
+            client.set_offset(-20).await;
+            assert_eq!(client.offset().await, -10);
+            client.set_offset(5).await;
+            assert_eq!(client.offset().await, 5);
+
+            assert_eq!(client.set_level(11).await, Err(ControllerOutOfRange(11)));
+            assert_eq!(client.set_level(10).await, Ok(()));
+
+            // The range is also enforced on the values set by the controller itself.
+            assert_eq!(client.force(30, 20).await, Err(ControllerOutOfRange(20)));
+            assert_eq!(client.offset().await, 10);
+            assert_eq!(client.level_now(), Some(10));
+        },
+    );
+}
+
+/// Test that derived fields are recomputed when their source fields are set.
+#[controller]
+mod derived_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        voltage: u32,
+        #[controller(setter)]
+        current: u32,
+        #[controller(derived(from = [voltage, current], with = "compute_power"), getter)]
+        power: u32,
+    }
+
+    impl Controller {
+        fn compute_power(voltage: &u32, current: &u32) -> u32 {
+            voltage * current
+        }
+    }
+}
+
+#[test]
+fn test_derived() {
+    firmware_controller::testing::with_client(
+        || derived_test_controller::Controller::new(5, 2),
+        |client| async move {
+            let mut power_stream = client.receive_power_changed().expect("Failed to subscribe");
+            assert_eq!(power_stream.next().await, Some(10));
+
+            client.set_voltage(12).await;
+            assert_eq!(power_stream.next().await, Some(24));
+
+            client.set_current(3).await;
+            assert_eq!(power_stream.next().await, Some(36));
+            assert_eq!(client.power().await, 36);
+        },
+    );
+}
+
+/// Test that atomic methods only broadcast the final values of the fields they set.
+#[controller]
+mod atomic_test_controller {
+    pub struct Controller {
+        #[controller(publish)]
+        mode: u8,
+        #[controller(publish)]
+        counter: u32,
+    }
+
+    impl Controller {
+        #[controller(atomic)]
+        pub async fn switch_mode(&mut self, mode: u8) {
+            // Go through an intermediate mode that nobody should observe.
+            self.set_mode(0).await;
+            self.set_mode(mode).await;
+            self.set_counter(self.counter + 1).await;
+        }
+    }
+}
+
+#[test]
+fn test_atomic() {
+    use atomic_test_controller::ControllerChange;
+
+    firmware_controller::testing::with_client(
+        || atomic_test_controller::Controller::new(1, 0),
+        |mut client| async move {
+            let mut change_stream = client.receive_changes().expect("Failed to subscribe");
+
+            client.switch_mode(2).await;
+            client.switch_mode(3).await;
+
+            for expected in [
+                ControllerChange::Mode(2),
+                ControllerChange::Counter(1),
+                ControllerChange::Mode(3),
+                ControllerChange::Counter(2),
+            ] {
+                let change = change_stream.next().await.expect("Should receive change");
+                assert_eq!(format!("{change:?}"), format!("{expected:?}"));
+            }
+        },
+    );
+}
+
+/// Test that snapshots of the fields are consistent.
+#[controller(snapshot(stream))]
+mod snapshot_test_controller {
+    pub struct Controller {
+        #[controller(publish)]
+        mode: u8,
+        #[controller(getter)]
+        counter: u32,
+    }
+
+    impl Controller {
+        pub async fn switch_mode(&mut self, mode: u8) {
+            self.counter += 1;
+            self.set_mode(mode).await;
+        }
+    }
+}
+
+#[test]
+fn test_snapshot() {
+    firmware_controller::testing::with_client(
+        || snapshot_test_controller::Controller::new(1, 0),
+        |mut client| async move {
+            let mut snapshot_stream = client.receive_snapshots().expect("Failed to subscribe");
+
+            let snapshot = snapshot_stream
+                .next()
+                .await
+                .expect("Should receive snapshot");
+            assert_eq!((snapshot.mode, snapshot.counter), (1, 0));
+
+            client.switch_mode(2).await;
+            let snapshot = client.snapshot().await;
+            assert_eq!((snapshot.mode, snapshot.counter), (2, 1));
+
+            let snapshot = snapshot_stream
+                .next()
+                .await
+                .expect("Should receive snapshot");
+            assert_eq!((snapshot.mode, snapshot.counter), (2, 1));
+        },
+    );
+}
+
+/// Test that the snapshot is available without its stream.
+#[controller]
+mod snapshot_only_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        mode: u8,
+        #[controller(getter)]
+        counter: u32,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_snapshot_without_stream() {
+    use snapshot_only_test_controller::Controller;
+
+    firmware_controller::testing::with_client(
+        || Controller::new(1, 5),
+        |client| async move {
+            client.set_mode(3).await;
+            let snapshot = client.snapshot().await;
+            assert_eq!((snapshot.mode, snapshot.counter), (3, 5));
+        },
+    );
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PowerMode {
+    Off,
+    On,
+}
+
+impl From<PowerMode> for u8 {
+    fn from(mode: PowerMode) -> u8 {
+        mode as u8
+    }
+}
+
+impl TryFrom<u8> for PowerMode {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, u8> {
+        match value {
+            0 => Ok(PowerMode::Off),
+            1 => Ok(PowerMode::On),
+            _ => Err(value),
+        }
+    }
+}
+
+/// Test that published fields backed by atomics can be read locally.
+#[controller]
+mod atomic_publish_test_controller {
+    use super::PowerMode;
+
+    pub struct Controller {
+        #[controller(publish(atomic), setter)]
+        enabled: bool,
+        #[controller(publish(atomic), setter)]
+        mode: PowerMode,
+        #[controller(publish(atomic, distinct), setter)]
+        level: i16,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_atomic_publish() {
+    firmware_controller::testing::with_client(
+        || atomic_publish_test_controller::Controller::new(false, PowerMode::Off, -3),
+        |client| async move {
+            assert_eq!(client.enabled_now(), Some(false));
+            assert_eq!(client.mode_now(), Some(PowerMode::Off));
+            assert_eq!(client.level_now(), Some(-3));
+
+            client.set_enabled(true).await;
+            client.set_mode(PowerMode::On).await;
+            client.set_level(1000).await;
+            assert_eq!(client.enabled_now(), Some(true));
+            assert_eq!(client.mode_now(), Some(PowerMode::On));
+            assert_eq!(client.level_now(), Some(1000));
+        },
+    );
+}
+
+/// Test waiting for published fields to match a condition.
+#[controller]
+mod wait_for_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u32,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_wait_for() {
+    firmware_controller::testing::with_client(
+        || wait_for_test_controller::Controller::new(1),
+        |client| async move {
+            // Resolves immediately if the current value already matches.
+            assert_eq!(client.wait_for_level_eq(1).await, 1);
+
+            let (level, _) = futures::join!(client.wait_for_level(|level| *level >= 5), async {
+                client.set_level(3).await;
+                client.set_level(7).await;
+            });
+            assert_eq!(level, 7);
+        },
+    );
+}
+
+/// Test subscribing once all the subscriber slots of a stream are taken.
+#[controller]
+mod subscribe_test_controller {
+    pub struct Controller {
+        #[controller(publish)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_subscribe() {
+    use firmware_controller::SubscribeError;
+
+    firmware_controller::testing::with_client(
+        || subscribe_test_controller::Controller::new(0),
+        |client| async move {
+            let mut streams: Vec<_> = (0..client.max_subscribers())
+                .map(|_| client.receive_level_changed().expect("Failed to subscribe"))
+                .collect();
+            assert_eq!(client.level_changed_subscriber_count(), streams.len());
+            assert_eq!(
+                client.receive_level_changed().err(),
+                Some(SubscribeError::MaxSubscribersReached),
+            );
+
+            // Waits until a slot is freed.
+            let (mut stream, _) = futures::join!(client.subscribe_level_changed(), async {
+                streams.pop();
+            });
+            assert_eq!(stream.next().await, Some(0));
+            assert_eq!(
+                client.level_changed_subscriber_count(),
+                client.max_subscribers()
+            );
+
+            drop(streams);
+            drop(stream);
+            assert_eq!(client.level_changed_subscriber_count(), 0);
+        },
+    );
+}
+
+/// Test that the client is a zero-sized, `Copy` handle that can be built in const context.
+#[controller]
+mod client_handle_test_controller {
+    pub struct Controller {
+        #[controller(setter, getter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+static CLIENT_HANDLE: client_handle_test_controller::ControllerClient =
+    client_handle_test_controller::ControllerClient::new();
+
+#[test]
+fn test_client_handle() {
+    use client_handle_test_controller::ControllerClient;
+
+    assert_eq!(core::mem::size_of::<ControllerClient>(), 0);
+
+    firmware_controller::testing::with_client(
+        || client_handle_test_controller::Controller::new(0),
+        |_| async move {
+            let client = CLIENT_HANDLE;
+            client.set_level(3).await;
+
+            // Copies and default-constructed clients talk to the same controller.
+            let copy = client;
+            assert_eq!(copy.level().await, 3);
+            let default: ControllerClient = Default::default();
+            assert_eq!(default.level().await, 3);
+        },
+    );
+}
+
+/// Test that consumers can be generic over the client API trait.
+#[controller]
+mod api_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+        #[controller(getter)]
+        boosted: bool,
+    }
+
+    impl Controller {
+        pub async fn boost(&mut self, amount: u8) -> u8 {
+            self.boosted = true;
+            let level = self.level.saturating_add(amount);
+            self.set_level(level).await;
+
+            level
+        }
+    }
+}
+
+async fn boost_until<C: api_test_controller::ControllerApi>(client: &mut C, target: u8) -> u8 {
+    let mut stream = client.receive_level_changed().expect("Failed to subscribe");
+    while stream.next().await.unwrap() < target {
+        client.boost(2).await;
+    }
+
+    client.wait_for_level(|level| *level >= target).await
+}
+
+#[test]
+fn test_client_api_trait() {
+    firmware_controller::testing::with_client(
+        || api_test_controller::Controller::new(1, false),
+        |mut client| async move {
+            assert_eq!(boost_until(&mut client, 6).await, 7);
+            assert!(api_test_controller::ControllerApi::boosted(&client).await);
+            api_test_controller::ControllerApi::set_level(&client, 0).await;
+            assert_eq!(client.level_now(), Some(0));
+        },
+    );
+}
+
+/// Test the mock of the client.
+#[controller(mock(cfg(feature = "testing")))]
+mod mock_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+        #[controller(getter)]
+        limit: u8,
+    }
+
+    impl Controller {
+        pub async fn boost(&mut self, amount: u8) -> bool {
+            let level = self.level.saturating_add(amount);
+            self.set_level(level).await;
+
+            level <= self.limit
+        }
+
+        #[controller(signal)]
+        pub async fn overheated(&self, temperature: i16);
+    }
+}
+
+/// A consumer of the controller, boosting until the limit and then stopping on overheating.
+async fn boost_to_limit<C: mock_test_controller::ControllerApi>(client: &mut C) -> i16 {
+    let mut overheated = client.receive_overheated().expect("Failed to subscribe");
+    let limit = client.limit().await;
+    while client.boost(1).await {}
+    client.set_level(limit).await;
+
+    overheated.next().await.unwrap().temperature
+}
+
+#[test]
+fn test_mock() {
+    use mock_test_controller::{ControllerCall, ControllerMock};
+
+    futures::executor::block_on(async {
+        let mut mock = ControllerMock::new();
+        mock.expect_limit(2);
+        mock.expect_boost(true).expect_boost(false);
+
+        let injector = mock.clone();
+        let (temperature, _) = futures::join!(boost_to_limit(&mut mock), async {
+            // The consumer is polled first, so it's already subscribed by now. Clones share the
+            // channels, so the injection doesn't need to be done through the same mock.
+            injector.inject_overheated(90);
+        });
+        assert_eq!(temperature, 90);
+        mock.verify();
+
+        let calls = mock.take_calls();
+        assert!(matches!(
+            calls.as_slice(),
+            [
+                ControllerCall::Limit,
+                ControllerCall::Boost { amount: 1 },
+                ControllerCall::Boost { amount: 1 },
+                ControllerCall::SetLevel { value: 2 },
+            ]
+        ));
+        assert!(mock.take_calls().is_empty());
+
+        // Injected values feed the published field's streams and local reads.
+        let mut level_stream = mock.receive_level_changed().expect("Failed to subscribe");
+        mock.inject_level(5);
+        assert_eq!(level_stream.next().await, Some(5));
+        assert_eq!(mock.level_now(), Some(5));
+
+        // Other mocks have their own channels.
+        let other = ControllerMock::new();
+        assert_eq!(other.level_now(), None);
+        other.inject_level(7);
+        assert_eq!(mock.level_now(), Some(5));
+        assert_eq!(mock.level_changed_subscriber_count(), 1);
+        assert_eq!(other.level_changed_subscriber_count(), 0);
+    });
+}
+
+/// Test the blocking client.
+#[controller(std)]
+mod blocking_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+    }
+
+    impl Controller {
+        pub async fn double(&mut self) -> u8 {
+            let level = self.level.saturating_mul(2);
+            self.set_level(level).await;
+            self.doubled(level).await;
+
+            level
+        }
+
+        #[controller(signal)]
+        pub async fn doubled(&self, level: u8);
+    }
+}
+
+#[test]
+fn test_blocking_client() {
+    // The blocking client can't be used from within an async test, so only run the controller.
+    let _controller = firmware_controller::testing::spawn_controller(|| {
+        blocking_test_controller::Controller::new(1)
+    });
+
+    let client = blocking_test_controller::ControllerBlockingClient::new();
+    let mut levels = client.receive_level_changed().expect("Failed to subscribe");
+    let mut doubled = client.subscribe_doubled();
+    assert_eq!(levels.next(), Some(1));
+
+    client.set_level(3);
+    assert_eq!(levels.next(), Some(3));
+
+    let mut client = client;
+    assert_eq!(client.double(), 6);
+    assert_eq!(levels.next(), Some(6));
+    assert_eq!(doubled.next().map(|args| args.level), Some(6));
+    assert_eq!(client.wait_for_level_eq(6), 6);
+    assert_eq!(client.level_now(), Some(6));
+    assert_eq!(client.doubled_subscriber_count(), 1);
+}
+
+/// Test driving the controller without an executor.
+#[controller]
+mod process_pending_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+        #[controller(getter)]
+        presses: u32,
+    }
+
+    impl Controller {
+        pub async fn press(&mut self) -> u32 {
+            self.presses += 1;
+
+            self.presses
+        }
+    }
+}
+
+#[test]
+fn test_process_pending() {
+    use futures::FutureExt;
+
+    let mut controller = process_pending_test_controller::Controller::new(0, 0);
+    let client = process_pending_test_controller::ControllerClient::new();
+    // The client is `Copy`, so each pending call can have its own.
+    let (mut presser, mut follow_up_presser) = (client, client);
+
+    futures::executor::block_on(async {
+        // Nothing to do yet.
+        assert_eq!(controller.process_pending().await, 0);
+
+        // The requests are queued, the responses only come once the controller processes them.
+        let mut press = Box::pin(presser.press());
+        let mut set_level = Box::pin(client.set_level(3));
+        assert!((&mut press).now_or_never().is_none());
+        assert!((&mut set_level).now_or_never().is_none());
+        assert_eq!(controller.process_pending().await, 2);
+        assert_eq!(press.await, 1);
+        set_level.await;
+        assert_eq!(client.level_now(), Some(3));
+
+        // Follow-up requests are handled until the clients are done.
+        let (presses, _) = futures::join!(
+            async {
+                follow_up_presser.press().await;
+                follow_up_presser.press().await;
+                client.presses().await
+            },
+            controller.run_until_idle(),
+        );
+        assert_eq!(presses, 3);
+    });
+}
+
+/// Test the testing helpers.
+#[controller]
+mod harness_test_controller {
+    pub struct Controller {
+        #[controller(getter)]
+        presses: u32,
+    }
+
+    impl Controller {
+        pub async fn press(&mut self) -> u32 {
+            self.presses += 1;
+
+            self.presses
+        }
+
+        #[controller(signal(sticky))]
+        pub async fn pressed(&self, presses: u32);
+    }
+}
+
+#[test]
+fn test_testing_helpers() {
+    use firmware_controller::testing::{block_on_timeout, with_client};
+    use futures::FutureExt;
+    use harness_test_controller::{Controller, ControllerClient};
+    use std::time::Duration;
+
+    let presses = with_client(
+        || Controller::new(0),
+        |mut client| async move {
+            client.press().await;
+            client.press().await
+        },
+    );
+    assert_eq!(presses, 2);
+
+    // Leave a request and a sticky signal behind, as if a test had bailed out early.
+    let mut client = ControllerClient::new();
+    assert!(Box::pin(client.press()).now_or_never().is_none());
+    let controller = Controller::new(0);
+    block_on_timeout(Duration::from_secs(10), controller.pressed(1));
+
+    // The next run starts afresh.
+    with_client(
+        move || controller,
+        |client| async move {
+            assert_eq!(client.presses().await, 0);
+            let mut pressed = client.receive_pressed().expect("Failed to subscribe");
+            assert!(pressed.next().now_or_never().is_none());
+        },
+    );
+}
+
+/// Test that resetting a controller drops the last values kept in its statics.
+#[controller(snapshot(stream))]
+mod reset_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_controller_reset() {
+    use futures::FutureExt;
+    use reset_test_controller::{Controller, ControllerClient};
+
+    // Building the controller broadcasts the initial values of its fields.
+    let _controller = Controller::new(3);
+    let client = ControllerClient::new();
+    assert_eq!(client.level_now(), Some(3));
+    let mut snapshots = client.receive_snapshots().expect("Failed to subscribe");
+    assert_eq!(
+        snapshots.next().now_or_never().flatten().map(|s| s.level),
+        Some(3)
+    );
+    drop(snapshots);
+
+    <Controller as firmware_controller::Controller>::reset();
+    assert_eq!(client.level_now(), None);
+    let mut levels = client.receive_level_changed().expect("Failed to subscribe");
+    assert!(levels.next().now_or_never().is_none());
+    let mut snapshots = client.receive_snapshots().expect("Failed to subscribe");
+    assert!(snapshots.next().now_or_never().is_none());
+}
+
+/// Test that a test waiting for its turn doesn't build its controller early.
+#[controller]
+mod harness_lock_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_testing_helpers_lock() {
+    use firmware_controller::testing::with_client;
+    use harness_lock_test_controller::Controller;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    static OTHER_BUILT: AtomicBool = AtomicBool::new(false);
+
+    let other = with_client(
+        || Controller::new(1),
+        |client| async move {
+            let other = std::thread::spawn(|| {
+                with_client(
+                    || {
+                        OTHER_BUILT.store(true, Ordering::SeqCst);
+                        Controller::new(2)
+                    },
+                    |client| async move { client.level_now() },
+                )
+            });
+            // Give the other test plenty of time to build its controller, were it built early. A
+            // slow machine can only make this miss an early build, not fail spuriously.
+            std::thread::sleep(Duration::from_millis(500));
+            assert!(!OTHER_BUILT.load(Ordering::SeqCst));
+            assert_eq!(client.level_now(), Some(1));
+
+            other
+        },
+    );
+    assert_eq!(other.join().unwrap(), Some(2));
+    assert!(OTHER_BUILT.load(Ordering::SeqCst));
+}
+
+#[test]
+#[should_panic(expected = "timed out")]
+fn test_testing_helpers_timeout() {
+    firmware_controller::testing::block_on_timeout(
+        std::time::Duration::from_millis(10),
+        futures::future::pending::<()>(),
+    );
+}
+
+// The crate, re-exported under another name.
+mod reexport {
+    pub use firmware_controller as fc;
+}
+
+/// Test a custom path to the crate.
+#[controller(crate = "crate::reexport::fc")]
+mod crate_path_test_controller {
+    pub struct Controller {
+        #[controller(publish, setter)]
+        level: u8,
+    }
+
+    impl Controller {}
+}
+
+#[test]
+fn test_crate_path() {
+    use reexport::fc::testing::with_client;
+
+    with_client(
+        || crate_path_test_controller::Controller::new(1),
+        |client| async move {
+            let mut levels = client.receive_level_changed().expect("Failed to subscribe");
+            assert_eq!(levels.next().await, Some(1));
+            client.set_level(2).await;
+            assert_eq!(levels.next().await, Some(2));
+        },
+    );
+}
+
+/// Test the streams being of the generic runtime types.
+#[controller]
+mod stream_types_test_controller {
+    pub struct Controller {
+        #[controller(publish(transitions), setter)]
+        level: u8,
+    }
+
+    impl Controller {
+        #[controller(signal)]
+        pub async fn overflow(&self, level: u8);
+    }
+}
+
+#[test]
+fn test_stream_types() {
+    use firmware_controller::{Published, SignalStream};
+    use stream_types_test_controller::Controller;
+
+    // Generic over the streams, whatever the controller.
+    async fn next_level<S: futures::Stream<Item = u8> + Unpin>(stream: &mut S) -> Option<u8> {
+        stream.next().await
+    }
+
+    firmware_controller::testing::with_client(
+        || Controller::new(1),
+        |client| async move {
+            let mut levels: Published<u8> = client.receive_level_changed().unwrap();
+            let mut transitions: SignalStream<(u8, u8)> =
+                client.receive_level_transitions().unwrap();
+            let _overflows: SignalStream<stream_types_test_controller::ControllerOverflowArgs> =
+                client.receive_overflow().unwrap();
+            assert_eq!(next_level(&mut levels).await, Some(1));
+
+            client.set_level(2).await;
+            assert_eq!(next_level(&mut levels).await, Some(2));
+            assert_eq!(transitions.next().await, Some((1, 2)));
+            assert_eq!(levels.wait_for(|level| *level == 2).await, 2);
+        },
+    );
+}
+
+#[derive(Debug)]
+pub struct LevelError;
+
+/// Test a controller module with its own `Result` alias.
+#[controller(std)]
+mod result_alias_test_controller {
+    use super::LevelError;
+
+    pub type Result<T> = core::result::Result<T, LevelError>;
+
+    pub struct Controller {
+        #[controller(publish)]
+        level: u8,
+    }
+
+    impl Controller {
+        pub async fn raise(&mut self) -> Result<u8> {
+            let level = self.level.checked_add(1).ok_or(LevelError)?;
+            self.set_level(level).await;
+
+            Ok(level)
+        }
+
+        #[controller(signal)]
+        pub async fn raised(&self, level: u8);
+    }
+}
+
+#[test]
+fn test_result_alias() {
+    use result_alias_test_controller::Controller;
+
+    firmware_controller::testing::with_client(
+        || Controller::new(254),
+        |mut client| async move {
+            let mut levels = client.receive_level_changed().unwrap();
+            assert_eq!(levels.next().await, Some(254));
+            assert_eq!(client.raise().await.unwrap(), 255);
+            assert_eq!(levels.next().await, Some(255));
+            assert!(client.raise().await.is_err());
+        },
+    );
+}
+
+/// Test that invalid controller definitions are rejected at compile time.
+#[test]
+fn test_compile_errors() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,13 @@
+use firmware_controller::controller;
+
+#[controller]
+mod controller {
+    pub struct Controller {
+        #[controller(publish, alarm(above = 80, clear_below = 85))]
+        current: u32,
+    }
+
+    impl Controller {}
+}
+
+fn main() {}